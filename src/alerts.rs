@@ -0,0 +1,560 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::models::{Alert, AlertState, SystemInfo};
+use crate::procfs;
+
+/// Resolved alerts kept for `/api/v1/alerts?state=resolved`
+const RESOLVED_HISTORY: usize = 100;
+
+/// Metric families with one value per instance, as `(prefix, field in the sample, key of each instance)`.
+/// `disk./.used_pct` is the `used_pct` of the disk whose `mount_point` is `/`.
+const FAMILIES: &[(&str, &str, &str)] = &[
+    ("disk", "disks", "mount_point"),
+    ("diskio", "disk_io", "name"),
+    ("temp", "components", "label"),
+    ("container", "containers", "name"),
+    ("user", "users", "name"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Self::Gt => value > threshold,
+            Self::Ge => value >= threshold,
+            Self::Lt => value < threshold,
+            Self::Le => value <= threshold,
+            Self::Eq => value == threshold,
+            Self::Ne => value != threshold,
+        }
+    }
+}
+
+/// `[name =] metric op threshold [for duration]`, e.g. `root_full = disk./.used_pct > 85 for 5m`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    pub name: String,
+    pub metric: String,
+    pub op: Op,
+    pub threshold: f64,
+    /// How long the condition must hold before the alert fires
+    pub for_duration: Duration,
+    /// The rule as written, without the name
+    pub expr: String,
+}
+
+impl std::str::FromStr for AlertRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+        let (name, tokens) = match tokens.as_slice() {
+            [name, "=", rest @ ..] => (Some(name.to_string()), rest),
+            rest => (None, rest),
+        };
+        let (metric, op, threshold, for_duration) = match tokens {
+            [metric, op, threshold] => (metric, op, threshold, None),
+            [metric, op, threshold, "for", duration] => (metric, op, threshold, Some(duration)),
+            _ => anyhow::bail!("invalid alert rule {:?}, expected `[name =] metric op threshold [for duration]`", s),
+        };
+        let op = match *op {
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            "==" => Op::Eq,
+            "!=" => Op::Ne,
+            other => anyhow::bail!("invalid operator {:?} in alert rule {:?}", other, s),
+        };
+        let threshold = threshold
+            .parse()
+            .map_err(|_| anyhow::anyhow!("invalid threshold {:?} in alert rule {:?}", threshold, s))?;
+        let for_duration = for_duration
+            .map(|d| parse_duration(d).ok_or_else(|| anyhow::anyhow!("invalid duration {:?} in alert rule {:?}", d, s)))
+            .transpose()?
+            .unwrap_or_default();
+
+        let expr = tokens.join(" ");
+        Ok(Self {
+            name: name.unwrap_or_else(|| expr.clone()),
+            metric: metric.to_string(),
+            op,
+            threshold,
+            for_duration,
+            expr,
+        })
+    }
+}
+
+/// `30s`, `5m`, `2h`, `7d`, or plain seconds
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let (number, unit) = s.find(|c: char| !c.is_ascii_digit()).map_or((s, ""), |i| s.split_at(i));
+    let number: u64 = number.parse().ok()?;
+    let secs = match unit {
+        "" | "s" => number,
+        "m" => number * 60,
+        "h" => number * 3600,
+        "d" => number * 86400,
+        _ => return None,
+    };
+    Some(Duration::from_secs(secs))
+}
+
+/// Parse `;`- or newline-separated rules, skipping blank lines and `#` comments
+pub fn parse_rules(s: &str) -> anyhow::Result<Vec<AlertRule>> {
+    s.split([';', '\n'])
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::parse)
+        .collect()
+}
+
+/// Split a family metric into `(prefix, instance pattern, field)`,
+/// e.g. `disk./var.used_pct` into `disk`, `/var` and `used_pct`
+fn split_family(metric: &str) -> Option<(&str, &str, &str)> {
+    let (prefix, rest) = metric.split_once('.')?;
+    if prefix != "net" && !FAMILIES.iter().any(|(family, _, _)| *family == prefix) {
+        return None;
+    }
+    let (pattern, name) = rest.rsplit_once('.')?;
+    Some((prefix, pattern, name))
+}
+
+/// The metric with its instance replaced by `*`, and the instance pattern.
+/// `disk./var.used_pct` gives `disk.*.used_pct` and `/var`; plain metrics have no instance.
+pub fn split_instance(metric: &str) -> (String, Option<&str>) {
+    match split_family(metric) {
+        Some((prefix, pattern, name)) => (format!("{}.*.{}", prefix, name), Some(pattern)),
+        None => (metric.to_string(), None),
+    }
+}
+
+/// Values of `metric` in the sample, one per instance (`None` for plain metrics).
+/// Plain metrics are dotted paths into the sample as served by `/api/v1/system`,
+/// such as `cpu.global_usage` or `processes.health.states.zombie`.
+pub fn resolve(sample: &Value, metric: &str) -> Vec<(Option<String>, f64)> {
+    let family = split_family(metric).and_then(|(prefix, pattern, name)| {
+        let (_, field, key) = FAMILIES.iter().find(|(family, _, _)| *family == prefix)?;
+        Some((field, key, pattern, name))
+    });
+    if let Some((field, key, pattern, name)) = family {
+        return sample[*field]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| {
+                let instance = item[*key].as_str()?;
+                let value = item[name].as_f64()?;
+                procfs::glob_match(pattern, instance).then(|| (Some(instance.to_string()), value))
+            })
+            .collect();
+    }
+    // Interfaces are keyed by name rather than listed
+    if let Some(("net", pattern, name)) = split_family(metric) {
+        return sample["networks"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(interface, _)| procfs::glob_match(pattern, interface))
+            .filter_map(|(interface, item)| Some((Some(interface.clone()), item[name].as_f64()?)))
+            .collect();
+    }
+
+    metric
+        .split('.')
+        .try_fold(sample, |value, key| value.get(key))
+        .and_then(Value::as_f64)
+        .map(|value| vec![(None, value)])
+        .unwrap_or_default()
+}
+
+/// Whether `metric` names a value in the sample, so misspelled rules can be reported.
+/// Families and interfaces without any instance right now can't be checked and count as known,
+/// as do process states, which only appear while some process is in them.
+pub fn is_known_metric(sample: &Value, metric: &str) -> bool {
+    if let Some((prefix, _, name)) = split_family(metric) {
+        let first = match prefix {
+            "net" => sample["networks"].as_object().and_then(|networks| networks.values().next()),
+            _ => FAMILIES
+                .iter()
+                .find(|(family, _, _)| *family == prefix)
+                .and_then(|(_, field, _)| sample[*field].as_array()?.first()),
+        };
+        return first.is_none_or(|item| item.get(name).is_some());
+    }
+    metric.starts_with("processes.health.states.") || !resolve(sample, metric).is_empty()
+}
+
+/// The sample as JSON, with percentages that aren't stored added for rules and history to use
+pub fn sample_value(info: &SystemInfo) -> Value {
+    let mut sample = serde_json::to_value(info).unwrap_or_default();
+    let pct = |used: u64, total: u64| if total > 0 { used as f64 / total as f64 * 100.0 } else { 0.0 };
+
+    let memory = &info.memory;
+    sample["memory"]["used_pct"] = pct(memory.total.saturating_sub(memory.available), memory.total).into();
+    sample["memory"]["swap_used_pct"] = pct(memory.swap_used, memory.swap_total).into();
+    if let Some(disks) = sample["disks"].as_array_mut() {
+        for (disk, info) in disks.iter_mut().zip(&info.disks) {
+            disk["used_pct"] = pct(info.total_space.saturating_sub(info.available_space), info.total_space).into();
+            disk["inodes_used_pct"] = pct(info.used_inodes, info.total_inodes).into();
+        }
+    }
+    sample
+}
+
+/// Where one rule instance stands
+struct Tracked {
+    state: AlertState,
+    value: f64,
+    since: DateTime<Utc>,
+    fired_at: Option<DateTime<Utc>>,
+}
+
+/// Evaluates the rules against each sample and keeps track of alert states
+pub struct AlertEngine {
+    state: Mutex<EngineState>,
+}
+
+struct EngineState {
+    rules: Vec<AlertRule>,
+    /// Set when rules are loaded, until their metrics have been checked against a sample
+    unchecked: bool,
+    /// Pending and firing alerts by rule name and instance
+    active: HashMap<(String, Option<String>), Tracked>,
+    resolved: VecDeque<Alert>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            state: Mutex::new(EngineState {
+                rules,
+                unchecked: true,
+                active: HashMap::new(),
+                resolved: VecDeque::with_capacity(RESOLVED_HISTORY),
+            }),
+        }
+    }
+
+    /// Replace the rules after a config reload. Alerts of unchanged rules keep their state;
+    /// those of removed or changed rules are dropped without resolving.
+    pub fn set_rules(&self, rules: Vec<AlertRule>) {
+        let mut state = self.state.lock().unwrap();
+        if state.rules == rules {
+            return;
+        }
+        let EngineState { rules: current, active, .. } = &mut *state;
+        active.retain(|(name, _), _| {
+            let new = rules.iter().find(|rule| &rule.name == name);
+            new.is_some_and(|rule| current.contains(rule))
+        });
+        tracing::info!("Alert rules reloaded ({} rule(s))", rules.len());
+        state.rules = rules;
+        state.unchecked = true;
+    }
+
+    pub fn rules(&self) -> Vec<AlertRule> {
+        self.state.lock().unwrap().rules.clone()
+    }
+
+    /// Check every rule against `info` and return the alerts that started firing or resolved
+    pub fn evaluate(&self, info: &SystemInfo) -> Vec<Alert> {
+        if self.state.lock().unwrap().rules.is_empty() {
+            return Vec::new();
+        }
+        self.evaluate_sample(&sample_value(info), info.timestamp)
+    }
+
+    /// `evaluate` on a sample as from `sample_value`
+    fn evaluate_sample(&self, sample: &Value, now: DateTime<Utc>) -> Vec<Alert> {
+        let mut state = self.state.lock().unwrap();
+        let EngineState { rules, unchecked, active, resolved } = &mut *state;
+        if std::mem::take(unchecked) {
+            for rule in rules.iter().filter(|rule| !is_known_metric(sample, &rule.metric)) {
+                tracing::warn!(
+                    "Alert rule {} uses metric {:?}, which doesn't exist in the sample; it will never fire",
+                    rule.name,
+                    rule.metric
+                );
+            }
+        }
+
+        let mut transitions = Vec::new();
+        let mut seen = HashSet::new();
+        for rule in rules.iter() {
+            for (instance, value) in resolve(sample, &rule.metric) {
+                if !rule.op.holds(value, rule.threshold) {
+                    continue;
+                }
+                let key = (rule.name.clone(), instance);
+                let tracked = active.entry(key.clone()).or_insert(Tracked {
+                    state: AlertState::Pending,
+                    value,
+                    since: now,
+                    fired_at: None,
+                });
+                tracked.value = value;
+                let held = (now - tracked.since).to_std().unwrap_or_default();
+                if tracked.state == AlertState::Pending && held >= rule.for_duration {
+                    tracked.state = AlertState::Firing;
+                    tracked.fired_at = Some(now);
+                    transitions.push(alert(rule, &key.1, tracked, None));
+                }
+                seen.insert(key);
+            }
+        }
+
+        // Whatever no longer matches, or no longer exists, is over
+        let ended: Vec<_> = active.keys().filter(|key| !seen.contains(key)).cloned().collect();
+        for key in ended {
+            let Some(tracked) = active.remove(&key) else { continue };
+            if tracked.state != AlertState::Firing {
+                continue;
+            }
+            let Some(rule) = rules.iter().find(|rule| rule.name == key.0) else { continue };
+            let alert = alert(rule, &key.1, &tracked, Some(now));
+            if resolved.len() == RESOLVED_HISTORY {
+                resolved.pop_front();
+            }
+            resolved.push_back(alert.clone());
+            transitions.push(alert);
+        }
+
+        for alert in &transitions {
+            let instance = alert.instance.as_deref().map(|i| format!(" for {}", i)).unwrap_or_default();
+            match alert.state {
+                AlertState::Firing => tracing::warn!(
+                    "Alert {} firing{}: value {}, threshold {}",
+                    alert.rule,
+                    instance,
+                    alert.value,
+                    alert.threshold
+                ),
+                _ => tracing::info!("Alert {} resolved{}", alert.rule, instance),
+            }
+        }
+        transitions
+    }
+
+    /// Pending and firing alerts, plus recently resolved ones if `include_resolved`
+    pub fn alerts(&self, include_resolved: bool) -> Vec<Alert> {
+        let state = self.state.lock().unwrap();
+        let mut alerts: Vec<Alert> = state
+            .active
+            .iter()
+            .filter_map(|((name, instance), tracked)| {
+                let rule = state.rules.iter().find(|rule| &rule.name == name)?;
+                Some(alert(rule, instance, tracked, None))
+            })
+            .collect();
+        alerts.sort_by(|a, b| (&a.rule, &a.instance).cmp(&(&b.rule, &b.instance)));
+        if include_resolved {
+            alerts.extend(state.resolved.iter().rev().cloned());
+        }
+        alerts
+    }
+}
+
+fn alert(rule: &AlertRule, instance: &Option<String>, tracked: &Tracked, resolved_at: Option<DateTime<Utc>>) -> Alert {
+    Alert {
+        rule: rule.name.clone(),
+        expr: rule.expr.clone(),
+        instance: instance.clone(),
+        state: if resolved_at.is_some() { AlertState::Resolved } else { tracked.state },
+        value: tracked.value,
+        threshold: rule.threshold,
+        since: tracked.since,
+        fired_at: tracked.fired_at,
+        resolved_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(s: &str) -> AlertRule {
+        s.parse().unwrap()
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    fn sample(cpu: f64) -> Value {
+        json!({
+            "cpu": {"global_usage": cpu},
+            "processes": {"health": {"states": {"sleeping": 12}}},
+            "disks": [
+                {"mount_point": "/", "used_pct": 91.0},
+                {"mount_point": "/var", "used_pct": 40.0},
+            ],
+            "disk_io": [],
+            "networks": {"eth0": {"received_bytes_per_sec": 1000.0}, "lo": {"received_bytes_per_sec": 5.0}},
+        })
+    }
+
+    fn states(transitions: &[Alert]) -> Vec<(&str, AlertState)> {
+        transitions.iter().map(|alert| (alert.rule.as_str(), alert.state)).collect()
+    }
+
+    #[test]
+    fn parses_rules() {
+        let named = rule("root_full = disk./.used_pct > 85 for 5m");
+        assert_eq!(named.name, "root_full");
+        assert_eq!(named.metric, "disk./.used_pct");
+        assert_eq!(named.op, Op::Gt);
+        assert_eq!(named.threshold, 85.0);
+        assert_eq!(named.for_duration, Duration::from_secs(300));
+        assert_eq!(named.expr, "disk./.used_pct > 85 for 5m");
+
+        let unnamed = rule("memory.used_pct >= 90.5");
+        assert_eq!(unnamed.name, "memory.used_pct >= 90.5");
+        assert_eq!(unnamed.op, Op::Ge);
+        assert_eq!(unnamed.for_duration, Duration::ZERO);
+
+        let ops: Vec<Op> = ["<", "<=", "==", "!="].iter().map(|op| rule(&format!("a {} 1", op)).op).collect();
+        assert_eq!(ops, [Op::Lt, Op::Le, Op::Eq, Op::Ne]);
+
+        let rules = parse_rules("# comment\ncpu.global_usage > 90; \n\nload = cpu.load_average.one > 8 for 10m").unwrap();
+        let names: Vec<_> = rules.iter().map(|rule| rule.name.as_str()).collect();
+        assert_eq!(names, ["cpu.global_usage > 90", "load"]);
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        for (s, error) in [
+            ("cpu.global_usage => 90", "invalid operator"),
+            ("cpu.global_usage > ninety", "invalid threshold"),
+            ("cpu.global_usage > 90 for 5w", "invalid duration"),
+            ("cpu.global_usage > 90 for", "invalid alert rule"),
+            ("cpu.global_usage > 90 during 5m", "invalid alert rule"),
+            ("cpu.global_usage", "invalid alert rule"),
+        ] {
+            let e = s.parse::<AlertRule>().unwrap_err().to_string();
+            assert!(e.contains(error), "{:?}: {}", s, e);
+        }
+        assert!(parse_rules("cpu.global_usage > 90; bad").is_err());
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("45"), Some(Duration::from_secs(45)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("7d"), Some(Duration::from_secs(7 * 86400)));
+        for bad in ["", "m", "5w", "1.5h", "-5m", "5 m"] {
+            assert_eq!(parse_duration(bad), None, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn fires_after_the_for_duration_and_resolves() {
+        let engine = AlertEngine::new(vec![rule("busy = cpu.global_usage > 80 for 2m")]);
+
+        assert!(engine.evaluate_sample(&sample(90.0), at(0)).is_empty());
+        assert!(engine.evaluate_sample(&sample(95.0), at(60)).is_empty());
+        let alerts = engine.alerts(false);
+        assert_eq!(states(&alerts), [("busy", AlertState::Pending)]);
+        assert_eq!(alerts[0].value, 95.0);
+        assert_eq!(alerts[0].since, at(0));
+
+        let fired = engine.evaluate_sample(&sample(92.0), at(120));
+        assert_eq!(states(&fired), [("busy", AlertState::Firing)]);
+        assert_eq!(fired[0].fired_at, Some(at(120)));
+        // Firing is only reported once
+        assert!(engine.evaluate_sample(&sample(92.0), at(180)).is_empty());
+
+        let resolved = engine.evaluate_sample(&sample(50.0), at(240));
+        assert_eq!(states(&resolved), [("busy", AlertState::Resolved)]);
+        assert_eq!(resolved[0].resolved_at, Some(at(240)));
+        assert!(engine.alerts(false).is_empty());
+        assert_eq!(states(&engine.alerts(true)), [("busy", AlertState::Resolved)]);
+    }
+
+    #[test]
+    fn pending_alerts_end_without_resolving() {
+        let engine = AlertEngine::new(vec![rule("busy = cpu.global_usage > 80 for 5m")]);
+        engine.evaluate_sample(&sample(90.0), at(0));
+        assert!(engine.evaluate_sample(&sample(10.0), at(60)).is_empty());
+        assert!(engine.alerts(true).is_empty());
+    }
+
+    #[test]
+    fn tracks_each_instance_of_a_family() {
+        let engine = AlertEngine::new(vec![rule("full = disk.*.used_pct > 85")]);
+        let fired = engine.evaluate_sample(&sample(0.0), at(0));
+        let instances: Vec<_> = fired.iter().map(|alert| alert.instance.as_deref()).collect();
+        assert_eq!(instances, [Some("/")]);
+    }
+
+    #[test]
+    fn keeps_alert_state_across_a_reload() {
+        let busy = rule("busy = cpu.global_usage > 80");
+        let full = rule("full = disk./.used_pct > 85");
+        let engine = AlertEngine::new(vec![busy.clone(), full]);
+        assert_eq!(engine.evaluate_sample(&sample(90.0), at(0)).len(), 2);
+
+        // `busy` is unchanged and keeps firing; `full` changed and starts over
+        engine.set_rules(vec![busy, rule("full = disk./.used_pct > 80 for 1m")]);
+        assert!(engine.evaluate_sample(&sample(90.0), at(60)).is_empty());
+        let alerts = engine.alerts(false);
+        assert_eq!(states(&alerts), [("busy", AlertState::Firing), ("full", AlertState::Pending)]);
+        assert_eq!(alerts[0].since, at(0));
+        assert_eq!(alerts[1].since, at(60));
+
+        // Dropped rules don't resolve
+        engine.set_rules(vec![]);
+        assert!(engine.alerts(true).is_empty());
+    }
+
+    #[test]
+    fn resolves_family_interface_and_plain_metrics() {
+        let sample = sample(42.0);
+        assert_eq!(resolve(&sample, "cpu.global_usage"), [(None, 42.0)]);
+        assert_eq!(resolve(&sample, "processes.health.states.sleeping"), [(None, 12.0)]);
+        assert!(resolve(&sample, "cpu.missing").is_empty());
+
+        assert_eq!(resolve(&sample, "disk./var.used_pct"), [(Some("/var".to_string()), 40.0)]);
+        assert_eq!(resolve(&sample, "disk.*.used_pct").len(), 2);
+        assert!(resolve(&sample, "disk./data.used_pct").is_empty());
+
+        assert_eq!(
+            resolve(&sample, "net.eth*.received_bytes_per_sec"),
+            [(Some("eth0".to_string()), 1000.0)]
+        );
+        assert_eq!(resolve(&sample, "net.*.received_bytes_per_sec").len(), 2);
+    }
+
+    #[test]
+    fn reports_unknown_metrics() {
+        let sample = sample(42.0);
+        for known in [
+            "cpu.global_usage",
+            "disk./data.used_pct",
+            "net.*.received_bytes_per_sec",
+            "diskio.*.utilization",
+            "processes.health.states.zombie",
+        ] {
+            assert!(is_known_metric(&sample, known), "{}", known);
+        }
+        for unknown in ["cpu.global_usag", "disk.*.usedpct", "net.eth0.rx", "disks./.used_pct"] {
+            assert!(!is_known_metric(&sample, unknown), "{}", unknown);
+        }
+    }
+}
@@ -27,3 +27,14 @@ impl<T> Resp<T> {
         }
     }
 }
+
+// Map a service error to a response code: bad input and missing resources
+// are the caller's problem, everything else is ours
+pub fn error_code(e: &anyhow::Error) -> u32 {
+    match e.downcast_ref::<std::io::Error>().map(std::io::Error::kind) {
+        Some(std::io::ErrorKind::InvalidInput) => 400,
+        Some(std::io::ErrorKind::NotFound) => 404,
+        Some(std::io::ErrorKind::PermissionDenied) => 403,
+        _ => 500,
+    }
+}
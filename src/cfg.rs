@@ -1,3 +1,14 @@
+use anyhow::Context;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::alerts::{self, AlertRule};
+use crate::control::ControlPolicy;
+use crate::history;
+use crate::notify::{self, WebhookConfig};
+use crate::procfs::{HostPaths, MountFilter};
+
 #[derive(Debug, Clone)]
 pub(crate) struct Config {
     pub(crate) server_host: String,
@@ -5,11 +16,47 @@ pub(crate) struct Config {
     pub(crate) mcp_port: u16,
     pub(crate) username: String,
     pub(crate) password: String,
+    /// `username:password` granting the admin scope needed for process control
+    pub(crate) admin_credentials: Option<String>,
     pub(crate) rate_limit: usize,
     pub(crate) mcp_mode: McpMode,
+    /// How long in-flight connections may keep running after a shutdown signal
+    pub(crate) shutdown_timeout: Duration,
+    /// How often the background sampler refreshes the system snapshot
+    pub(crate) sample_interval: Duration,
+    /// `tracing` filter directives, e.g. `info` or `sysinfo_api=debug`
+    pub(crate) log_filter: String,
+    /// Unix socket path for the REST API, served alongside or instead of TCP
+    pub(crate) server_socket: Option<PathBuf>,
+    /// Unix socket path for the MCP HTTP server
+    pub(crate) mcp_socket: Option<PathBuf>,
+    /// Permission bits applied to created sockets, e.g. `0660`
+    pub(crate) socket_mode: Option<u32>,
+    /// Numeric `uid[:gid]` applied to created sockets
+    pub(crate) socket_owner: Option<(Option<u32>, Option<u32>)>,
+    /// Peer UIDs allowed over Unix sockets without basic auth
+    pub(crate) socket_trusted_uids: Vec<u32>,
+    /// Skip the TCP listeners and only serve the configured Unix sockets
+    pub(crate) disable_tcp: bool,
+    /// Mounted host `/proc`, `/sys` and root to collect from instead of our own
+    pub(crate) host_paths: HostPaths,
+    /// Filesystem types and mount-point globs left out of the disk list
+    pub(crate) mount_filter: MountFilter,
+    /// Docker-compatible API socket used to look up container names and images
+    pub(crate) container_runtime_socket: Option<PathBuf>,
+    /// Whether processes may be signalled and reniced, and which ones
+    pub(crate) control_policy: ControlPolicy,
+    /// Threshold rules evaluated against every sample
+    pub(crate) alert_rules: Vec<AlertRule>,
+    /// Where firing and resolved alerts are POSTed
+    pub(crate) webhooks: WebhookConfig,
+    /// SQLite database sampled metrics are kept in; no history without one
+    pub(crate) history_db: Option<PathBuf>,
+    /// Metrics written to the history database, named as in alert rules
+    pub(crate) history_metrics: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) enum McpMode {
     Stdio,
     Http,
@@ -17,9 +64,51 @@ pub(crate) enum McpMode {
     RestOnly,
 }
 
+/// Path of the dotenv-style config file, re-read on SIGHUP
+pub(crate) fn config_file() -> PathBuf {
+    std::env::var("CONFIG_FILE")
+        .unwrap_or_else(|_| ".env".to_string())
+        .into()
+}
+
+/// Variables set in the dotenv-style config file at `path`
+pub(crate) fn read_file(path: &Path) -> anyhow::Result<HashMap<String, String>> {
+    dotenvy::from_path_iter(path)
+        .with_context(|| format!("failed to open {}", path.display()))?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Keys of the config file whose effective value differs between `old` and `new`, and
+/// keys whose file value changed but stays overridden by the process environment
+pub(crate) fn changed_keys(old: &HashMap<String, String>, new: &HashMap<String, String>) -> (Vec<String>, Vec<String>) {
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    let (mut changed, mut overridden) = (Vec::new(), Vec::new());
+    for key in keys {
+        if old.get(key) == new.get(key) {
+            continue;
+        }
+        if std::env::var_os(key).is_some() {
+            overridden.push(key.clone());
+        } else {
+            changed.push(key.clone());
+        }
+    }
+    (changed, overridden)
+}
+
 impl Config {
-    pub(crate) fn from_env() -> Self {
-        let mcp_mode = match std::env::var("MCP_MODE").unwrap_or_else(|_| "both".to_string()).to_lowercase().as_str() {
+    /// Settings from the process environment, falling back to the config file's `vars`.
+    /// Startup and reloads use the same precedence, so a reload never replaces a value
+    /// set in the environment.
+    pub(crate) fn from_vars(vars: &HashMap<String, String>) -> anyhow::Result<Self> {
+        Self::from_lookup(|key| std::env::var(key).ok().or_else(|| vars.get(key).cloned()))
+    }
+
+    fn from_lookup(var: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let mcp_mode = match var("MCP_MODE").unwrap_or_else(|| "both".to_string()).to_lowercase().as_str() {
             "stdio" => McpMode::Stdio,
             "http" => McpMode::Http,
             "both" => McpMode::Both,
@@ -27,23 +116,254 @@ impl Config {
             _ => McpMode::Both,
         };
 
-        Self {
-            server_host: std::env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
-            server_port: std::env::var("SERVER_PORT")
-                .unwrap_or_else(|_| "8080".to_string())
+        let default_filter = MountFilter::default();
+        let mount_filter = MountFilter {
+            fs_types: var("DISK_EXCLUDE_FS_TYPES")
+                .and_then(|s| parse_list(&s).ok())
+                .unwrap_or(default_filter.fs_types),
+            mount_points: var("DISK_EXCLUDE_MOUNTS")
+                .and_then(|s| parse_list(&s).ok())
+                .unwrap_or(default_filter.mount_points),
+        };
+
+        let list = |key: &str| var(key).and_then(|s| parse_list(&s).ok()).unwrap_or_default();
+        let control_policy = ControlPolicy {
+            enabled: var("PROCESS_CONTROL_ENABLED").is_some_and(|s| parse_bool(&s)),
+            mcp: var("PROCESS_CONTROL_MCP").is_some_and(|s| parse_bool(&s)),
+            allow_users: list("PROCESS_CONTROL_ALLOW_USERS"),
+            deny_users: list("PROCESS_CONTROL_DENY_USERS"),
+            allow_exes: list("PROCESS_CONTROL_ALLOW_EXES"),
+            deny_exes: list("PROCESS_CONTROL_DENY_EXES"),
+            audit_log: var("PROCESS_CONTROL_AUDIT_LOG").filter(|s| !s.is_empty()).map(PathBuf::from),
+        };
+
+        // Rules from the file come first, then the inline ones
+        let mut alert_rules = match var("ALERT_RULES_FILE").filter(|s| !s.is_empty()) {
+            Some(path) => {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read ALERT_RULES_FILE {}", path))?;
+                alerts::parse_rules(&content).with_context(|| format!("invalid rule in {}", path))?
+            }
+            None => Vec::new(),
+        };
+        alert_rules.extend(alerts::parse_rules(&var("ALERT_RULES").unwrap_or_default()).context("invalid rule in ALERT_RULES")?);
+        if let Some(duplicate) = alert_rules
+            .iter()
+            .enumerate()
+            .find_map(|(i, rule)| alert_rules[..i].iter().any(|other| other.name == rule.name).then_some(&rule.name))
+        {
+            anyhow::bail!("duplicate alert rule name {:?}", duplicate);
+        }
+
+        let template = match (var("WEBHOOK_TEMPLATE").filter(|s| !s.is_empty()), var("WEBHOOK_TEMPLATE_FILE").filter(|s| !s.is_empty())) {
+            (Some(template), _) => Some(notify::parse_template(&template).context("invalid WEBHOOK_TEMPLATE")?),
+            (None, Some(path)) => {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read WEBHOOK_TEMPLATE_FILE {}", path))?;
+                Some(notify::parse_template(&content).with_context(|| format!("invalid template in {}", path))?)
+            }
+            (None, None) => None,
+        };
+        let webhook_urls: Vec<String> = list("WEBHOOK_URLS");
+        if let Some(url) = webhook_urls.iter().find(|url| !url.starts_with("http://") && !url.starts_with("https://")) {
+            anyhow::bail!("invalid webhook URL {:?}, expected http:// or https://", url);
+        }
+        let webhooks = WebhookConfig {
+            urls: webhook_urls,
+            secret: var("WEBHOOK_SECRET").filter(|s| !s.is_empty()),
+            template,
+            retries: var("WEBHOOK_RETRIES")
+                .map(|s| s.parse())
+                .transpose()
+                .context("WEBHOOK_RETRIES must be a number")?
+                .unwrap_or(5),
+            dedup_window: Duration::from_secs(
+                var("WEBHOOK_DEDUP_SECS")
+                    .map(|s| s.parse())
+                    .transpose()
+                    .context("WEBHOOK_DEDUP_SECS must be a number of seconds")?
+                    .unwrap_or(300),
+            ),
+        };
+
+        let username = var("AUTH_USERNAME").unwrap_or("admin".to_string());
+        let password = var("AUTH_PASSWORD").unwrap_or("password123".to_string());
+        let admin_credentials = var("ADMIN_PASSWORD")
+            .filter(|s| !s.is_empty())
+            .map(|admin_password| {
+                let admin_username = var("ADMIN_USERNAME").unwrap_or_else(|| "root".to_string());
+                anyhow::ensure!(
+                    admin_username != username || admin_password != password,
+                    "ADMIN_USERNAME/ADMIN_PASSWORD must differ from AUTH_USERNAME/AUTH_PASSWORD"
+                );
+                Ok(format!("{}:{}", admin_username, admin_password))
+            })
+            .transpose()?;
+
+        let config = Self {
+            server_host: var("SERVER_HOST").unwrap_or_else(|| "0.0.0.0".to_string()),
+            server_port: var("SERVER_PORT")
+                .unwrap_or_else(|| "8080".to_string())
                 .parse()
-                .expect("SERVER_PORT must be a valid port number"),
-            mcp_port: std::env::var("MCP_PORT")
-                .unwrap_or_else(|_| "8081".to_string())
+                .context("SERVER_PORT must be a valid port number")?,
+            mcp_port: var("MCP_PORT")
+                .unwrap_or_else(|| "8081".to_string())
                 .parse()
-                .expect("MCP_PORT must be a valid port number"),
-            username: std::env::var("AUTH_USERNAME").unwrap_or("admin".to_string()),
-            password: std::env::var("AUTH_PASSWORD").unwrap_or("password123".to_string()),
-            rate_limit: std::env::var("RATE_LIMIT")
-                .ok()
+                .context("MCP_PORT must be a valid port number")?,
+            username,
+            password,
+            admin_credentials,
+            rate_limit: var("RATE_LIMIT")
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(100),
             mcp_mode,
+            shutdown_timeout: Duration::from_secs(
+                var("SHUTDOWN_TIMEOUT_SECS")
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(10),
+            ),
+            sample_interval: Duration::from_secs(
+                var("SAMPLE_INTERVAL_SECS")
+                    .and_then(|s| s.parse().ok())
+                    .filter(|secs| *secs > 0)
+                    .unwrap_or(5),
+            ),
+            log_filter: var("RUST_LOG").unwrap_or_else(|| "info".to_string()),
+            server_socket: var("SERVER_SOCKET").filter(|s| !s.is_empty()).map(PathBuf::from),
+            mcp_socket: var("MCP_SOCKET").filter(|s| !s.is_empty()).map(PathBuf::from),
+            socket_mode: var("SOCKET_MODE")
+                .map(|s| u32::from_str_radix(s.trim_start_matches("0o"), 8))
+                .transpose()
+                .context("SOCKET_MODE must be an octal permission mode such as 0660")?,
+            socket_owner: var("SOCKET_OWNER")
+                .map(|s| parse_owner(&s))
+                .transpose()
+                .context("SOCKET_OWNER must be a numeric uid[:gid]")?,
+            socket_trusted_uids: var("SOCKET_TRUSTED_UIDS")
+                .map(|s| parse_list(&s))
+                .transpose()
+                .context("SOCKET_TRUSTED_UIDS must be a comma-separated list of numeric uids")?
+                .unwrap_or_default(),
+            disable_tcp: var("DISABLE_TCP").is_some_and(|s| parse_bool(&s)),
+            host_paths: HostPaths::new(
+                var("HOST_PROC").filter(|s| !s.is_empty()).map(PathBuf::from),
+                var("HOST_SYS").filter(|s| !s.is_empty()).map(PathBuf::from),
+                var("HOST_ROOT").filter(|s| !s.is_empty()).map(PathBuf::from),
+            ),
+            mount_filter,
+            container_runtime_socket: var("CONTAINER_RUNTIME_SOCKET").filter(|s| !s.is_empty()).map(PathBuf::from),
+            control_policy,
+            alert_rules,
+            webhooks,
+            history_db: var("HISTORY_DB").filter(|s| !s.is_empty()).map(PathBuf::from),
+            history_metrics: var("HISTORY_METRICS")
+                .and_then(|s| parse_list(&s).ok())
+                .filter(|metrics: &Vec<String>| !metrics.is_empty())
+                .unwrap_or_else(|| history::DEFAULT_METRICS.iter().map(|metric| metric.to_string()).collect()),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check settings that are only unsafe in combination; also re-run after a reload has
+    /// carried over the startup settings
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        // MCP callers present no credentials, so process control over MCP is only allowed
+        // where the transport itself restricts who can connect
+        if self.control_policy.mcp {
+            let restricted = match self.mcp_mode {
+                McpMode::Stdio | McpMode::RestOnly => true,
+                McpMode::Http | McpMode::Both => {
+                    self.disable_tcp && self.mcp_socket.is_some() && !self.socket_trusted_uids.is_empty()
+                }
+            };
+            anyhow::ensure!(
+                restricted,
+                "PROCESS_CONTROL_MCP requires MCP_MODE=stdio, or DISABLE_TCP with MCP_SOCKET and SOCKET_TRUSTED_UIDS, \
+                 since the MCP HTTP listener has no authentication"
+            );
         }
+        Ok(())
     }
+
+    /// Names of settings that differ from `other` but only take effect after a restart
+    pub(crate) fn restart_required(&self, other: &Config) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        if self.server_host != other.server_host {
+            changed.push("SERVER_HOST");
+        }
+        if self.server_port != other.server_port {
+            changed.push("SERVER_PORT");
+        }
+        if self.mcp_port != other.mcp_port {
+            changed.push("MCP_PORT");
+        }
+        if self.mcp_mode != other.mcp_mode {
+            changed.push("MCP_MODE");
+        }
+        if self.shutdown_timeout != other.shutdown_timeout {
+            changed.push("SHUTDOWN_TIMEOUT_SECS");
+        }
+        if self.server_socket != other.server_socket {
+            changed.push("SERVER_SOCKET");
+        }
+        if self.mcp_socket != other.mcp_socket {
+            changed.push("MCP_SOCKET");
+        }
+        if self.socket_mode != other.socket_mode || self.socket_owner != other.socket_owner {
+            changed.push("SOCKET_MODE/SOCKET_OWNER");
+        }
+        if self.disable_tcp != other.disable_tcp {
+            changed.push("DISABLE_TCP");
+        }
+        if self.host_paths != other.host_paths {
+            changed.push("HOST_PROC/HOST_SYS/HOST_ROOT");
+        }
+        if self.container_runtime_socket != other.container_runtime_socket {
+            changed.push("CONTAINER_RUNTIME_SOCKET");
+        }
+        if self.history_db != other.history_db || self.history_metrics != other.history_metrics {
+            changed.push("HISTORY_DB/HISTORY_METRICS");
+        }
+        changed
+    }
+
+    /// Carry over the settings that only apply at startup from `current`
+    pub(crate) fn keep_startup_settings(&mut self, current: &Config) {
+        self.server_host = current.server_host.clone();
+        self.server_port = current.server_port;
+        self.mcp_port = current.mcp_port;
+        self.mcp_mode = current.mcp_mode.clone();
+        self.shutdown_timeout = current.shutdown_timeout;
+        self.server_socket = current.server_socket.clone();
+        self.mcp_socket = current.mcp_socket.clone();
+        self.socket_mode = current.socket_mode;
+        self.socket_owner = current.socket_owner;
+        self.disable_tcp = current.disable_tcp;
+        self.host_paths = current.host_paths.clone();
+        self.container_runtime_socket = current.container_runtime_socket.clone();
+        self.history_db = current.history_db.clone();
+        self.history_metrics = current.history_metrics.clone();
+    }
+}
+
+fn parse_bool(s: &str) -> bool {
+    matches!(s.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+fn parse_list<T: std::str::FromStr>(s: &str) -> Result<Vec<T>, T::Err> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+fn parse_owner(s: &str) -> Result<(Option<u32>, Option<u32>), std::num::ParseIntError> {
+    let (uid, gid) = s.split_once(':').unwrap_or((s, ""));
+    let parse = |id: &str| match id.trim() {
+        "" => Ok(None),
+        id => id.parse().map(Some),
+    };
+    Ok((parse(uid)?, parse(gid)?))
 }
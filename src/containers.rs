@@ -0,0 +1,323 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, Instant};
+
+use crate::models::{ContainerInfo, ContainerRef, ProcessInfo};
+use crate::procfs::{self, HostPaths};
+
+/// Container a cgroup path belongs to, as `(runtime, id)`.
+///
+/// Recognizes the layouts used by Docker (`/docker/<id>`, `docker-<id>.scope`),
+/// containerd and CRI-O under Kubernetes (`cri-containerd-<id>.scope`, `crio-<id>.scope`,
+/// `/kubepods/.../<id>`) and Podman (`libpod-<id>.scope`).
+pub fn parse_container_id(cgroup: &str) -> Option<(&'static str, String)> {
+    let mut components = cgroup.rsplit('/');
+    let last = components.next()?;
+    let parent = components.next().unwrap_or_default();
+
+    let name = last.strip_suffix(".scope").unwrap_or(last);
+    let (runtime, id) = [
+        ("docker-", "docker"),
+        ("cri-containerd-", "containerd"),
+        ("crio-", "cri-o"),
+        ("libpod-", "podman"),
+    ]
+    .into_iter()
+    .find_map(|(prefix, runtime)| Some((runtime, name.strip_prefix(prefix)?)))
+    .or_else(|| match parent {
+        "docker" => Some(("docker", name)),
+        _ if cgroup.contains("/kubepods") => Some(("kubernetes", name)),
+        _ => None,
+    })?;
+
+    let is_id = id.len() == 64 && id.bytes().all(|b| b.is_ascii_hexdigit());
+    is_id.then(|| (runtime, id.to_string()))
+}
+
+/// Name and image reported by the container runtime
+#[derive(Debug, Clone, PartialEq)]
+struct RuntimeDetails {
+    name: String,
+    image: String,
+}
+
+/// How long a failed runtime lookup is remembered before the container is asked about again
+const RETRY_AFTER: Duration = Duration::from_secs(60);
+
+enum Lookup {
+    /// Queued for the lookup thread
+    Pending,
+    Found(RuntimeDetails),
+    Failed(Instant),
+}
+
+/// Tags processes with their container and sums usage per container.
+/// Runtime lookups run on a background thread so a slow runtime never holds up a
+/// sample; until one completes the container is listed without a name and image.
+/// Successful lookups are cached while the container runs, failed ones are retried
+/// after `RETRY_AFTER`.
+pub struct ContainerTracker {
+    /// Shared with the lookup thread
+    details: Arc<Mutex<HashMap<String, Lookup>>>,
+    /// `None` without a runtime socket
+    queue: Option<mpsc::Sender<String>>,
+    retry_after: Duration,
+}
+
+impl ContainerTracker {
+    pub fn new(runtime_socket: Option<PathBuf>) -> Self {
+        let details = Arc::new(Mutex::new(HashMap::new()));
+        let queue = runtime_socket.and_then(|socket| {
+            let (tx, rx) = mpsc::channel::<String>();
+            let details = details.clone();
+            let spawned = std::thread::Builder::new()
+                .name("container-lookup".to_string())
+                .spawn(move || lookup_loop(&socket, &rx, &details));
+            match spawned {
+                Ok(_) => Some(tx),
+                Err(e) => {
+                    tracing::warn!("Failed to start container runtime lookups: {}", e);
+                    None
+                }
+            }
+        });
+        Self {
+            details,
+            queue,
+            retry_after: RETRY_AFTER,
+        }
+    }
+
+    /// Cached details of container `id`, queueing a lookup if there are none yet
+    /// or the last one failed long enough ago
+    fn details(&self, id: &str) -> Option<RuntimeDetails> {
+        let queue = self.queue.as_ref()?;
+        let mut details = self.details.lock().unwrap();
+        match details.get(id) {
+            Some(Lookup::Found(found)) => return Some(found.clone()),
+            Some(Lookup::Pending) => return None,
+            Some(Lookup::Failed(at)) if at.elapsed() < self.retry_after => return None,
+            _ => {}
+        }
+        if queue.send(id.to_string()).is_ok() {
+            details.insert(id.to_string(), Lookup::Pending);
+        }
+        None
+    }
+
+    pub fn attribute(&mut self, processes: &mut [ProcessInfo], paths: &HostPaths) -> Vec<ContainerInfo> {
+        let mut containers: HashMap<String, ContainerInfo> = HashMap::new();
+        for process in processes.iter_mut() {
+            let Some(cgroup) = procfs::read_pid_cgroup(paths, process.pid).ok().flatten() else {
+                continue;
+            };
+            let Some((runtime, id)) = parse_container_id(&cgroup) else {
+                continue;
+            };
+
+            let details = self.details(&id);
+            let container = ContainerRef {
+                id: id.clone(),
+                runtime: runtime.to_string(),
+                name: details.as_ref().map(|d| d.name.clone()),
+                image: details.map(|d| d.image),
+            };
+
+            let entry = containers.entry(id).or_insert_with(|| ContainerInfo {
+                id: container.id.clone(),
+                runtime: container.runtime.clone(),
+                name: container.name.clone(),
+                image: container.image.clone(),
+                cpu_usage: 0.0,
+                memory: 0,
+                process_count: 0,
+            });
+            entry.cpu_usage += process.cpu_usage;
+            entry.memory += process.memory;
+            entry.process_count += 1;
+            process.container = Some(container);
+        }
+
+        // Forget containers that have gone away so a reused ID is looked up again
+        let running: HashSet<&String> = containers.keys().collect();
+        self.details.lock().unwrap().retain(|id, _| running.contains(id));
+
+        let mut containers: Vec<ContainerInfo> = containers.into_values().collect();
+        containers.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
+        containers
+    }
+}
+
+/// Answer queued lookups one at a time until the tracker is dropped.
+/// Results for containers that went away in the meantime are discarded.
+fn lookup_loop(socket: &Path, rx: &mpsc::Receiver<String>, details: &Mutex<HashMap<String, Lookup>>) {
+    while let Ok(id) = rx.recv() {
+        let result = match inspect(socket, &id) {
+            Some(found) => Lookup::Found(found),
+            None => Lookup::Failed(Instant::now()),
+        };
+        if let Some(entry) = details.lock().unwrap().get_mut(&id) {
+            *entry = result;
+        }
+    }
+}
+
+/// Ask a Docker-compatible API (Docker, Podman) on `socket` about container `id`
+fn inspect(socket: &Path, id: &str) -> Option<RuntimeDetails> {
+    match request_inspect(socket, id) {
+        Ok(details) => details,
+        Err(e) => {
+            tracing::debug!("Container runtime lookup of {} via {} failed: {}", id, socket.display(), e);
+            None
+        }
+    }
+}
+
+fn request_inspect(socket: &Path, id: &str) -> std::io::Result<Option<RuntimeDetails>> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    stream.set_write_timeout(Some(Duration::from_secs(1)))?;
+    // HTTP/1.0 so the runtime closes the connection and we can read to the end
+    write!(stream, "GET /containers/{}/json HTTP/1.0\r\nHost: localhost\r\n\r\n", id)?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+
+    let response = String::from_utf8_lossy(&response);
+    let Some((head, body)) = response.split_once("\r\n\r\n") else {
+        return Ok(None);
+    };
+    if !head.starts_with("HTTP/1.0 200") && !head.starts_with("HTTP/1.1 200") {
+        return Ok(None);
+    }
+    let json: serde_json::Value = serde_json::from_str(body)?;
+    Ok(Some(RuntimeDetails {
+        name: json["Name"].as_str().unwrap_or_default().trim_start_matches('/').to_string(),
+        image: json["Config"]["Image"].as_str().unwrap_or_default().to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+
+    const ID: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    /// A proc tree with one process in a Docker container
+    fn proc_tree(dir: &Path) -> HostPaths {
+        let proc = dir.join("proc");
+        std::fs::create_dir_all(proc.join("42")).unwrap();
+        std::fs::write(
+            proc.join("42/cgroup"),
+            format!("0::/system.slice/docker-{}.scope\n", ID),
+        )
+        .unwrap();
+        HostPaths::new(Some(proc), None, Some(dir.to_path_buf()))
+    }
+
+    fn process() -> ProcessInfo {
+        ProcessInfo {
+            pid: 42,
+            name: "nginx".to_string(),
+            cpu_usage: 1.5,
+            memory: 1024,
+            uid: Some(0),
+            user: None,
+            container: None,
+            start_time: None,
+            state: None,
+            ppid: None,
+        }
+    }
+
+    /// Attribute until the lookup thread has answered
+    fn attribute_until_named(tracker: &mut ContainerTracker, paths: &HostPaths) -> Vec<ContainerInfo> {
+        for _ in 0..200 {
+            let containers = tracker.attribute(&mut [process()], paths);
+            if containers[0].name.is_some() {
+                return containers;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("container was never named");
+    }
+
+    #[test]
+    fn names_containers_from_the_runtime_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = proc_tree(dir.path());
+        let socket = dir.path().join("docker.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let n = stream.read(&mut request).unwrap();
+            let body = r#"{"Name":"/web","Config":{"Image":"nginx:1.27"}}"#;
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+            String::from_utf8_lossy(&request[..n]).into_owned()
+        });
+
+        let mut tracker = ContainerTracker::new(Some(socket));
+        let mut processes = [process()];
+        // The first sample doesn't wait for the runtime
+        let containers = tracker.attribute(&mut processes, &paths);
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].id, ID);
+        assert_eq!(containers[0].runtime, "docker");
+        assert_eq!(containers[0].process_count, 1);
+        assert_eq!(processes[0].container.as_ref().unwrap().id, ID);
+
+        let containers = attribute_until_named(&mut tracker, &paths);
+        assert_eq!(containers[0].name.as_deref(), Some("web"));
+        assert_eq!(containers[0].image.as_deref(), Some("nginx:1.27"));
+        let request = server.join().unwrap();
+        assert!(request.starts_with(&format!("GET /containers/{}/json HTTP/1.0\r\n", ID)));
+    }
+
+    #[test]
+    fn retries_failed_lookups() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = proc_tree(dir.path());
+        let socket = dir.path().join("docker.sock");
+
+        // Nothing listens yet, so the first lookup fails
+        let mut tracker = ContainerTracker::new(Some(socket.clone()));
+        tracker.attribute(&mut [process()], &paths);
+        for _ in 0..200 {
+            if matches!(tracker.details.lock().unwrap().get(ID), Some(Lookup::Failed(_))) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(matches!(tracker.details.lock().unwrap().get(ID), Some(Lookup::Failed(_))));
+        // Not asked again before the retry delay
+        tracker.attribute(&mut [process()], &paths);
+        assert!(matches!(tracker.details.lock().unwrap().get(ID), Some(Lookup::Failed(_))));
+
+        let listener = UnixListener::bind(&socket).unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]).unwrap();
+            let body = r#"{"Name":"/db","Config":{"Image":"postgres:17"}}"#;
+            write!(stream, "HTTP/1.0 200 OK\r\n\r\n{}", body).unwrap();
+        });
+        tracker.retry_after = Duration::ZERO;
+        let containers = attribute_until_named(&mut tracker, &paths);
+        assert_eq!(containers[0].name.as_deref(), Some("db"));
+        assert_eq!(containers[0].image.as_deref(), Some("postgres:17"));
+    }
+
+    #[test]
+    fn forgets_containers_that_stopped() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = proc_tree(dir.path());
+        let mut tracker = ContainerTracker::new(Some(dir.path().join("missing.sock")));
+        tracker.attribute(&mut [process()], &paths);
+        assert!(tracker.details.lock().unwrap().contains_key(ID));
+        tracker.attribute(&mut [], &paths);
+        assert!(tracker.details.lock().unwrap().is_empty());
+    }
+}
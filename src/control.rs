@@ -0,0 +1,212 @@
+use chrono::Utc;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::procfs;
+
+/// Who may be signalled or reniced, and whether that is allowed at all.
+/// Deny entries win over allow entries; when any allow list is set a process
+/// must match one of them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ControlPolicy {
+    pub enabled: bool,
+    /// Also accept actions over MCP, which has no credentials of its own
+    pub mcp: bool,
+    /// User names or numeric UIDs
+    pub allow_users: Vec<String>,
+    pub deny_users: Vec<String>,
+    /// Executable path globs, as for the disk mount exclusions
+    pub allow_exes: Vec<String>,
+    pub deny_exes: Vec<String>,
+    /// File every attempt is appended to as a JSON line, besides the `audit` log target
+    pub audit_log: Option<PathBuf>,
+}
+
+/// Signals the API can send; anything else is refused
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Signal {
+    Term,
+    Kill,
+    Stop,
+    Cont,
+    Hup,
+}
+
+impl std::str::FromStr for Signal {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.to_uppercase();
+        match upper.strip_prefix("SIG").unwrap_or(&upper) {
+            "TERM" => Ok(Self::Term),
+            "KILL" => Ok(Self::Kill),
+            "STOP" => Ok(Self::Stop),
+            "CONT" => Ok(Self::Cont),
+            "HUP" => Ok(Self::Hup),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported signal {:?}, expected TERM, KILL, STOP, CONT or HUP", s),
+            )),
+        }
+    }
+}
+
+impl Signal {
+    fn number(self) -> libc::c_int {
+        match self {
+            Self::Term => libc::SIGTERM,
+            Self::Kill => libc::SIGKILL,
+            Self::Stop => libc::SIGSTOP,
+            Self::Cont => libc::SIGCONT,
+            Self::Hup => libc::SIGHUP,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Term => "SIGTERM",
+            Self::Kill => "SIGKILL",
+            Self::Stop => "SIGSTOP",
+            Self::Cont => "SIGCONT",
+            Self::Hup => "SIGHUP",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Action {
+    Signal(Signal),
+    /// New nice value, -20 to 19
+    Renice(i32),
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Signal(signal) => f.write_str(signal.name()),
+            Self::Renice(nice) => write!(f, "renice {}", nice),
+        }
+    }
+}
+
+/// What the policy is checked against
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub pid: u32,
+    pub name: String,
+    pub uid: Option<u32>,
+    pub user: Option<String>,
+    pub exe: Option<String>,
+}
+
+impl ControlPolicy {
+    pub fn check(&self, target: &Target) -> io::Result<()> {
+        let user_matches = |list: &[String]| {
+            list.iter().any(|entry| {
+                target.user.as_deref() == Some(entry.as_str())
+                    || target.uid.is_some_and(|uid| entry.parse() == Ok(uid))
+            })
+        };
+        let exe_matches = |list: &[String]| {
+            target
+                .exe
+                .as_deref()
+                .is_some_and(|exe| list.iter().any(|pattern| procfs::glob_match(pattern, exe)))
+        };
+
+        let denied = if user_matches(&self.deny_users) || exe_matches(&self.deny_exes) {
+            Some("matches the deny list")
+        } else if (!self.allow_users.is_empty() || !self.allow_exes.is_empty())
+            && !user_matches(&self.allow_users)
+            && !exe_matches(&self.allow_exes)
+        {
+            Some("is not on the allow list")
+        } else {
+            None
+        };
+        match denied {
+            Some(reason) => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("process {} ({}) {}", target.pid, target.name, reason),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Send the signal or change the priority. The caller has already checked the policy.
+pub fn perform(pid: u32, action: Action) -> io::Result<()> {
+    let raw_pid = libc::pid_t::try_from(pid)
+        .ok()
+        .filter(|pid| *pid > 0)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid pid {}", pid)))?;
+    if let Action::Renice(nice) = action
+        && !(-20..=19).contains(&nice)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("nice value {} out of range, expected -20 to 19", nice),
+        ));
+    }
+    // SAFETY: plain syscalls on a positive pid, so never a process group or every process
+    let rc = match action {
+        Action::Signal(signal) => unsafe { libc::kill(raw_pid, signal.number()) },
+        Action::Renice(nice) => unsafe { libc::setpriority(libc::PRIO_PROCESS, raw_pid as libc::id_t, nice) },
+    };
+    if rc == 0 {
+        return Ok(());
+    }
+    let e = io::Error::last_os_error();
+    Err(match e.raw_os_error() {
+        Some(libc::ESRCH) => io::Error::new(io::ErrorKind::NotFound, format!("process {} not found", pid)),
+        Some(libc::EPERM) | Some(libc::EACCES) => io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("not permitted to apply {} to process {}", action, pid),
+        ),
+        _ => e,
+    })
+}
+
+/// Record an attempt, successful or not, on the `audit` log target and in the audit file
+pub fn audit(policy: &ControlPolicy, actor: &str, pid: u32, target: Option<&Target>, action: Action, outcome: &io::Result<()>) {
+    let name = target.map(|t| t.name.as_str());
+    let user = target.and_then(|t| t.user.as_deref());
+    let exe = target.and_then(|t| t.exe.as_deref());
+    let described = format!(
+        "{} on pid {} (name={}, user={}, exe={})",
+        action,
+        pid,
+        name.unwrap_or("-"),
+        user.unwrap_or("-"),
+        exe.unwrap_or("-")
+    );
+    match outcome {
+        Ok(()) => tracing::info!(target: "audit", "Process control by {}: {} succeeded", actor, described),
+        Err(e) => tracing::warn!(target: "audit", "Process control by {}: {} failed: {}", actor, described, e),
+    }
+
+    let Some(path) = &policy.audit_log else {
+        return;
+    };
+    let line = serde_json::json!({
+        "timestamp": Utc::now(),
+        "actor": actor,
+        "action": action.to_string(),
+        "pid": pid,
+        "name": name,
+        "user": user,
+        "uid": target.and_then(|t| t.uid),
+        "exe": exe,
+        "result": if outcome.is_ok() { "ok" } else { "error" },
+        "error": outcome.as_ref().err().map(|e| e.to_string()),
+    });
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = written {
+        tracing::error!("Failed to write audit log {}: {}", path.display(), e);
+    }
+}
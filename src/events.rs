@@ -0,0 +1,161 @@
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+use crate::models::{ProcessEvent, ProcessEventKind, ProcessInfo};
+use crate::procfs::{self, HostPaths};
+
+/// How many events are kept for `since` queries and SSE resumption
+const BUFFER_SIZE: usize = 1000;
+
+/// Where to resume reading events from
+#[derive(Debug, Clone, Copy)]
+pub enum Cursor {
+    /// Events with a greater ID
+    Id(u64),
+    /// Events detected after this time
+    Time(DateTime<Utc>),
+}
+
+impl std::str::FromStr for Cursor {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(id) = s.parse() {
+            return Ok(Self::Id(id));
+        }
+        DateTime::parse_from_rfc3339(s)
+            .map(|time| Self::Time(time.with_timezone(&Utc)))
+            .map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("invalid since {:?}, expected an event ID or an RFC 3339 timestamp", s),
+                )
+            })
+    }
+}
+
+/// What we remember about a running process to describe it once it's gone
+struct Tracked {
+    name: String,
+    cmdline: Vec<String>,
+    start_time: Option<u64>,
+}
+
+/// Process start/exit events found by diffing the process list between samples.
+/// Processes that start and exit within one sample interval are not seen.
+pub struct ProcessEvents {
+    state: Mutex<EventState>,
+    tx: broadcast::Sender<ProcessEvent>,
+}
+
+struct EventState {
+    /// `None` until the first sample, which only sets the baseline
+    known: Option<HashMap<u32, Tracked>>,
+    buffer: VecDeque<ProcessEvent>,
+    next_id: u64,
+}
+
+impl ProcessEvents {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(BUFFER_SIZE);
+        Self {
+            state: Mutex::new(EventState {
+                known: None,
+                buffer: VecDeque::with_capacity(BUFFER_SIZE),
+                next_id: 1,
+            }),
+            tx,
+        }
+    }
+
+    /// Compare `processes` with the previous sample and record what started and exited.
+    /// A PID that now belongs to a process with a different start time counts as both.
+    pub fn observe(&self, processes: &[ProcessInfo], paths: &HostPaths) {
+        let mut state = self.state.lock().unwrap();
+        let first = state.known.is_none();
+        let mut previous = state.known.take().unwrap_or_default();
+        let now = Utc::now();
+
+        let mut known = HashMap::with_capacity(processes.len());
+        let mut events = Vec::new();
+        for process in processes {
+            let tracked = match previous.remove(&process.pid) {
+                Some(tracked) if tracked.start_time == process.start_time => tracked,
+                reused => {
+                    if let Some(gone) = reused {
+                        events.push(exited(process.pid, gone, now));
+                    }
+                    let tracked = Tracked {
+                        name: process.name.clone(),
+                        cmdline: procfs::read_cmdline(paths, process.pid).unwrap_or_default(),
+                        start_time: process.start_time,
+                    };
+                    if !first {
+                        events.push(ProcessEvent {
+                            id: 0,
+                            kind: ProcessEventKind::Started,
+                            timestamp: now,
+                            pid: process.pid,
+                            name: tracked.name.clone(),
+                            cmdline: tracked.cmdline.clone(),
+                            start_time: tracked.start_time,
+                            runtime_secs: None,
+                        });
+                    }
+                    tracked
+                }
+            };
+            known.insert(process.pid, tracked);
+        }
+        events.extend(previous.into_iter().map(|(pid, gone)| exited(pid, gone, now)));
+        state.known = Some(known);
+
+        for mut event in events {
+            event.id = state.next_id;
+            state.next_id += 1;
+            if state.buffer.len() == BUFFER_SIZE {
+                state.buffer.pop_front();
+            }
+            state.buffer.push_back(event.clone());
+            // No subscribers is fine; the event is still buffered
+            let _ = self.tx.send(event);
+        }
+    }
+
+    /// Buffered events after `cursor` (all of them without one), optionally only for one process name
+    pub fn since(&self, cursor: Option<Cursor>, name: Option<&str>) -> Vec<ProcessEvent> {
+        let state = self.state.lock().unwrap();
+        state
+            .buffer
+            .iter()
+            .filter(|event| match cursor {
+                Some(Cursor::Id(id)) => event.id > id,
+                Some(Cursor::Time(time)) => event.timestamp > time,
+                None => true,
+            })
+            .filter(|event| name.is_none_or(|name| event.name == name))
+            .cloned()
+            .collect()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ProcessEvent> {
+        self.tx.subscribe()
+    }
+}
+
+fn exited(pid: u32, gone: Tracked, now: DateTime<Utc>) -> ProcessEvent {
+    ProcessEvent {
+        id: 0,
+        kind: ProcessEventKind::Exited,
+        timestamp: now,
+        pid,
+        name: gone.name,
+        cmdline: gone.cmdline,
+        start_time: gone.start_time,
+        runtime_secs: gone
+            .start_time
+            .map(|start| (now.timestamp().max(0) as u64).saturating_sub(start)),
+    }
+}
@@ -0,0 +1,261 @@
+use chrono::{DateTime, Utc};
+use std::io;
+
+use crate::models::{Forecast, HistorySeries};
+
+/// Fewer stored points than this give no forecast
+const MIN_POINTS: usize = 5;
+
+/// What can run out, each forecast from its stored usage percentage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resource {
+    Disk,
+    Memory,
+    Swap,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 3] = [Resource::Disk, Resource::Memory, Resource::Swap];
+
+    /// History series the forecast is computed from
+    pub fn metric(self) -> &'static str {
+        match self {
+            Self::Disk => "disk.*.used_pct",
+            Self::Memory => "memory.used_pct",
+            Self::Swap => "memory.swap_used_pct",
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Disk => "disk",
+            Self::Memory => "memory",
+            Self::Swap => "swap",
+        }
+    }
+}
+
+impl std::str::FromStr for Resource {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|resource| resource.as_str() == s).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown resource {:?}, expected disk, memory or swap", s),
+            )
+        })
+    }
+}
+
+/// Least-squares line through the points, with `t` in seconds relative to now
+struct Fit {
+    /// Change per second
+    slope: f64,
+    /// Standard error of the slope
+    slope_stderr: f64,
+    r_squared: f64,
+}
+
+fn fit(points: &[(f64, f64)]) -> Option<Fit> {
+    let n = points.len() as f64;
+    let mean_t = points.iter().map(|(t, _)| t).sum::<f64>() / n;
+    let mean_v = points.iter().map(|(_, v)| v).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|(t, v)| (t - mean_t) * (v - mean_v)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    let intercept = mean_v - slope * mean_t;
+    let ssr: f64 = points.iter().map(|(t, v)| (v - (intercept + slope * t)).powi(2)).sum();
+    let sst: f64 = points.iter().map(|(_, v)| (v - mean_v).powi(2)).sum();
+    Some(Fit {
+        slope,
+        slope_stderr: (ssr / (n - 2.0) / sxx).sqrt(),
+        // A perfectly flat series has no trend to explain
+        r_squared: if sst > 0.0 { 1.0 - ssr / sst } else { 0.0 },
+    })
+}
+
+/// When `series` will reach `threshold` percent if it keeps changing at its fitted rate.
+/// `None` if there are too few points to fit a line.
+pub fn forecast(resource: Resource, series: &HistorySeries, threshold: f64, now: DateTime<Utc>) -> Option<Forecast> {
+    if series.points.len() < MIN_POINTS {
+        return None;
+    }
+    let points: Vec<(f64, f64)> = series
+        .points
+        .iter()
+        .map(|point| ((point.timestamp - now).num_seconds() as f64, point.value))
+        .collect();
+    let fit = fit(&points)?;
+    // ETAs count from the last stored value, so they agree with "already at"
+    let current = series.points.last()?.value;
+    let reached = current >= threshold;
+
+    let remaining = threshold - current;
+    let secs_at = |slope: f64| (slope > 0.0).then(|| (remaining / slope).round() as u64);
+    let (eta_secs, eta_earliest_secs, eta_latest_secs) = if reached {
+        (Some(0), Some(0), Some(0))
+    } else {
+        (
+            secs_at(fit.slope),
+            secs_at(fit.slope + 2.0 * fit.slope_stderr),
+            secs_at(fit.slope - 2.0 * fit.slope_stderr),
+        )
+    };
+    let confidence = match (fit.r_squared, series.points.len()) {
+        (r, n) if r >= 0.8 && n >= 20 => "high",
+        (r, _) if r >= 0.5 => "medium",
+        _ => "low",
+    };
+
+    let rate_per_hour = fit.slope * 3600.0;
+    let subject = match (resource, &series.instance) {
+        (Resource::Disk, Some(mount_point)) => mount_point.clone(),
+        (Resource::Memory, _) => "Memory".to_string(),
+        _ => "Swap".to_string(),
+    };
+    let reaching = match (resource, threshold >= 100.0) {
+        (Resource::Disk, true) => "be full".to_string(),
+        (_, true) => "be exhausted".to_string(),
+        (_, false) => format!("reach {}%", threshold),
+    };
+    let summary = match eta_secs {
+        _ if reached => format!("{} is already at {:.1}% (threshold {}%)", subject, current, threshold),
+        Some(secs) => format!(
+            "{} will {} in {} at the current rate ({:+.2}%/h)",
+            subject,
+            reaching,
+            approx(secs),
+            rate_per_hour
+        ),
+        None => format!("{} usage is not rising ({:+.2}%/h), at {:.1}%", subject, rate_per_hour, current),
+    };
+
+    Some(Forecast {
+        resource: resource.as_str().to_string(),
+        instance: series.instance.clone(),
+        metric: series.metric.clone(),
+        current,
+        threshold,
+        rate_per_hour,
+        eta_secs,
+        eta: eta_secs
+            .and_then(|secs| chrono::Duration::try_seconds(i64::try_from(secs).ok()?))
+            .and_then(|eta| now.checked_add_signed(eta)),
+        eta_earliest_secs,
+        eta_latest_secs,
+        r_squared: fit.r_squared,
+        confidence: confidence.to_string(),
+        points: series.points.len(),
+        summary,
+    })
+}
+
+/// `~45m`, `~3h` or `~12d`
+fn approx(secs: u64) -> String {
+    match secs {
+        s if s < 3600 => format!("~{}m", (s as f64 / 60.0).round().max(1.0)),
+        s if s < 48 * 3600 => format!("~{}h", (s as f64 / 3600.0).round()),
+        s => format!("~{}d", (s as f64 / 86400.0).round()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::HistoryPoint;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
+    /// One point per minute over the last `values.len()` minutes, the last one now
+    fn series(values: &[f64]) -> HistorySeries {
+        let start = now() - chrono::Duration::minutes(values.len() as i64 - 1);
+        HistorySeries {
+            metric: "memory.used_pct".to_string(),
+            instance: None,
+            resolution: "1m".to_string(),
+            points: values
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| HistoryPoint {
+                    timestamp: start + chrono::Duration::minutes(i as i64),
+                    value,
+                    min: value,
+                    max: value,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn fits_a_linear_series() {
+        let points: Vec<(f64, f64)> = (0..10).map(|i| (i as f64 * 60.0 - 540.0, 50.0 + i as f64)).collect();
+        let fit = fit(&points).unwrap();
+        assert!((fit.slope - 1.0 / 60.0).abs() < 1e-9);
+        assert!(fit.slope_stderr < 1e-9);
+        assert!((fit.r_squared - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fits_a_flat_series() {
+        let points: Vec<(f64, f64)> = (0..10).map(|i| (i as f64 * 60.0, 40.0)).collect();
+        let fit = fit(&points).unwrap();
+        assert_eq!(fit.slope, 0.0);
+        assert_eq!(fit.r_squared, 0.0);
+    }
+
+    #[test]
+    fn fits_a_falling_series() {
+        let points: Vec<(f64, f64)> = (0..10).map(|i| (i as f64 * 60.0, 90.0 - 2.0 * i as f64)).collect();
+        let fit = fit(&points).unwrap();
+        assert!((fit.slope + 2.0 / 60.0).abs() < 1e-9);
+        assert!((fit.r_squared - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn forecasts_a_rising_series() {
+        // 1% per minute, at 59% now: 41 minutes to go
+        let values: Vec<f64> = (0..20).map(|i| 40.0 + i as f64).collect();
+        let forecast = forecast(Resource::Memory, &series(&values), 100.0, now()).unwrap();
+        assert_eq!(forecast.current, 59.0);
+        assert!((forecast.rate_per_hour - 60.0).abs() < 1e-6);
+        assert_eq!(forecast.eta_secs, Some(41 * 60));
+        assert_eq!(forecast.eta, Some(now() + chrono::Duration::minutes(41)));
+        assert_eq!(forecast.confidence, "high");
+        assert_eq!(forecast.summary, "Memory will be exhausted in ~41m at the current rate (+60.00%/h)");
+    }
+
+    #[test]
+    fn does_not_forecast_a_flat_or_falling_series() {
+        for values in [vec![40.0; 10], (0..10).map(|i| 90.0 - i as f64).collect()] {
+            let forecast = forecast(Resource::Memory, &series(&values), 100.0, now()).unwrap();
+            assert_eq!(forecast.eta_secs, None);
+            assert_eq!(forecast.eta_earliest_secs, None);
+            assert!(forecast.summary.contains("is not rising"), "{}", forecast.summary);
+        }
+    }
+
+    #[test]
+    fn already_at_only_once_the_last_value_reaches_the_threshold() {
+        // A jump late in the window puts the fitted line above the threshold while the last value is below
+        let below = forecast(Resource::Memory, &series(&[10.0, 10.0, 10.0, 99.0, 88.0]), 90.0, now()).unwrap();
+        assert_eq!(below.current, 88.0);
+        assert!(below.eta_secs.is_some_and(|secs| secs > 0));
+        assert!(!below.summary.contains("already"), "{}", below.summary);
+
+        let reached = forecast(Resource::Memory, &series(&[80.0, 85.0, 88.0, 90.0, 92.0]), 90.0, now()).unwrap();
+        assert_eq!(reached.eta_secs, Some(0));
+        assert_eq!(reached.summary, "Memory is already at 92.0% (threshold 90%)");
+    }
+
+    #[test]
+    fn needs_enough_points() {
+        let values = vec![50.0; MIN_POINTS - 1];
+        assert!(forecast(Resource::Memory, &series(&values), 100.0, now()).is_none());
+    }
+}
@@ -0,0 +1,422 @@
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::alerts;
+use crate::models::{HistoryPoint, HistorySeries, HistorySeriesInfo, SystemInfo};
+use crate::procfs;
+
+/// Metrics recorded when `HISTORY_METRICS` is unset
+pub const DEFAULT_METRICS: &[&str] = &[
+    "cpu.global_usage",
+    "cpu.load_average.one",
+    "memory.used_pct",
+    "memory.available",
+    "memory.swap_used",
+    "memory.swap_used_pct",
+    "disk.*.used_pct",
+    "disk.*.available_space",
+    "disk.*.inodes_used_pct",
+    "diskio.*.utilization",
+    "net.*.received_bytes_per_sec",
+    "net.*.transmitted_bytes_per_sec",
+    "temp.*.temperature",
+    "processes.total_count",
+];
+
+/// Seconds between deletions of points past their tier's retention
+const PRUNE_INTERVAL: i64 = 60;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS series (
+        id INTEGER PRIMARY KEY,
+        metric TEXT NOT NULL,
+        instance TEXT NOT NULL DEFAULT '',
+        UNIQUE (metric, instance)
+    );
+    CREATE TABLE IF NOT EXISTS points (
+        series_id INTEGER NOT NULL REFERENCES series (id),
+        resolution INTEGER NOT NULL,
+        bucket INTEGER NOT NULL,
+        sum REAL NOT NULL,
+        count INTEGER NOT NULL,
+        min REAL NOT NULL,
+        max REAL NOT NULL,
+        PRIMARY KEY (series_id, resolution, bucket)
+    ) WITHOUT ROWID;
+    -- Pruning deletes by tier and age across all series
+    CREATE INDEX IF NOT EXISTS points_resolution_bucket ON points (resolution, bucket);
+";
+
+/// Every sample is written to each tier at once, so rollups are always up to date
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    /// Kept for an hour
+    Raw,
+    /// One-minute averages, kept for a day
+    Minute,
+    /// Ten-minute averages, kept for 30 days
+    TenMinutes,
+}
+
+const TIERS: [Resolution; 3] = [Resolution::Raw, Resolution::Minute, Resolution::TenMinutes];
+
+impl Resolution {
+    /// Bucket width in seconds, also the tier's key in the database
+    fn secs(self) -> i64 {
+        match self {
+            Self::Raw => 1,
+            Self::Minute => 60,
+            Self::TenMinutes => 600,
+        }
+    }
+
+    fn retention_secs(self) -> i64 {
+        match self {
+            Self::Raw => 3600,
+            Self::Minute => 86400,
+            Self::TenMinutes => 30 * 86400,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Minute => "1m",
+            Self::TenMinutes => "10m",
+        }
+    }
+
+    /// The finest tier that still covers `from`
+    fn covering(from: i64, now: i64) -> Self {
+        TIERS
+            .into_iter()
+            .find(|tier| now - from <= tier.retention_secs())
+            .unwrap_or(Self::TenMinutes)
+    }
+}
+
+impl std::str::FromStr for Resolution {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TIERS.into_iter().find(|tier| tier.as_str() == s).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown resolution {:?}, expected raw, 1m or 10m", s),
+            )
+        })
+    }
+}
+
+/// An RFC 3339 timestamp, or a duration before `now` such as `6h` or `7d`
+pub fn parse_time(s: &str, now: DateTime<Utc>) -> io::Result<DateTime<Utc>> {
+    if let Some(ago) = alerts::parse_duration(s) {
+        return chrono::Duration::from_std(ago)
+            .ok()
+            .and_then(|ago| now.checked_sub_signed(ago))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("time {:?} is out of range", s)));
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid time {:?}, expected an RFC 3339 timestamp or a duration such as 6h", s),
+            )
+        })
+}
+
+/// Sampled metrics kept in SQLite across restarts, with rollups and retention
+pub struct HistoryStore {
+    /// Metrics as in alert rules, `*` matching any instance
+    metrics: Vec<String>,
+    state: Mutex<StoreState>,
+}
+
+struct StoreState {
+    conn: Connection,
+    /// Series IDs by metric and instance (`""` for plain metrics)
+    series_ids: HashMap<(String, String), i64>,
+    last_pruned: Option<i64>,
+}
+
+impl HistoryStore {
+    pub fn open(path: &Path, metrics: Vec<String>) -> anyhow::Result<Self> {
+        let conn = Connection::open(path).with_context(|| format!("failed to open history database {}", path.display()))?;
+        // WAL keeps readers from blocking the sampler's writes
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.execute_batch(SCHEMA)
+            .with_context(|| format!("failed to set up history database {}", path.display()))?;
+        tracing::info!("Recording {} metric(s) to {}", metrics.len(), path.display());
+        Ok(Self {
+            metrics,
+            state: Mutex::new(StoreState {
+                conn,
+                series_ids: HashMap::new(),
+                last_pruned: None,
+            }),
+        })
+    }
+
+    /// Write the sample to every tier, pruning expired points now and then.
+    /// Failures are logged rather than failing the sample.
+    pub fn record(&self, info: &SystemInfo) {
+        if let Err(e) = self.write(&alerts::sample_value(info), info.timestamp.timestamp()) {
+            tracing::warn!("Failed to write metric history: {}", e);
+        }
+    }
+
+    /// `sample` as from `alerts::sample_value`, taken at Unix time `now`
+    fn write(&self, sample: &serde_json::Value, now: i64) -> rusqlite::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let StoreState { conn, series_ids, last_pruned } = &mut *state;
+
+        let tx = conn.transaction()?;
+        {
+            let mut upsert = tx.prepare_cached(
+                "INSERT INTO points (series_id, resolution, bucket, sum, count, min, max)
+                 VALUES (?1, ?2, ?3, ?4, 1, ?4, ?4)
+                 ON CONFLICT (series_id, resolution, bucket) DO UPDATE SET
+                     sum = sum + excluded.sum,
+                     count = count + 1,
+                     min = min(min, excluded.min),
+                     max = max(max, excluded.max)",
+            )?;
+            for metric in &self.metrics {
+                let (name, _) = alerts::split_instance(metric);
+                for (instance, value) in alerts::resolve(sample, metric) {
+                    let key = (name.clone(), instance.unwrap_or_default());
+                    let id = match series_ids.get(&key) {
+                        Some(id) => *id,
+                        None => {
+                            let id = series_id(&tx, &key.0, &key.1)?;
+                            series_ids.insert(key, id);
+                            id
+                        }
+                    };
+                    for tier in TIERS {
+                        upsert.execute(params![id, tier.secs(), now - now.rem_euclid(tier.secs()), value])?;
+                    }
+                }
+            }
+        }
+        tx.commit()?;
+
+        if last_pruned.is_none_or(|pruned| now - pruned >= PRUNE_INTERVAL) {
+            let mut deleted = 0;
+            for tier in TIERS {
+                deleted += conn.execute(
+                    "DELETE FROM points WHERE resolution = ?1 AND bucket < ?2",
+                    params![tier.secs(), now - tier.retention_secs()],
+                )?;
+            }
+            if deleted > 0 {
+                tracing::debug!("Pruned {} expired history point(s)", deleted);
+            }
+            *last_pruned = Some(now);
+        }
+        Ok(())
+    }
+
+    /// Stored values of `metric` between `from` and `to`, one series per matching instance.
+    /// Without a resolution the finest tier that still covers `from` is used.
+    pub fn query(
+        &self,
+        metric: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        resolution: Option<Resolution>,
+    ) -> anyhow::Result<Vec<HistorySeries>> {
+        let (name, pattern) = alerts::split_instance(metric);
+        let (from, to) = (from.timestamp(), to.timestamp());
+        let resolution = resolution.unwrap_or_else(|| Resolution::covering(from, Utc::now().timestamp()));
+
+        let state = self.state.lock().unwrap();
+        let mut stmt = state.conn.prepare_cached(
+            "SELECT s.instance, p.bucket, p.sum / p.count, p.min, p.max
+             FROM points p JOIN series s ON s.id = p.series_id
+             WHERE s.metric = ?1 AND p.resolution = ?2 AND p.bucket > ?3 AND p.bucket <= ?4
+             ORDER BY s.instance, p.bucket",
+        )?;
+        let rows = stmt.query_map(params![name, resolution.secs(), from - resolution.secs(), to], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                HistoryPoint {
+                    timestamp: DateTime::from_timestamp(row.get(1)?, 0).unwrap_or_default(),
+                    value: row.get(2)?,
+                    min: row.get(3)?,
+                    max: row.get(4)?,
+                },
+            ))
+        })?;
+
+        let mut series: Vec<HistorySeries> = Vec::new();
+        for row in rows {
+            let (instance, point) = row?;
+            if pattern.is_some_and(|pattern| !procfs::glob_match(pattern, &instance)) {
+                continue;
+            }
+            let instance = pattern.map(|_| instance);
+            match series.last_mut() {
+                Some(last) if last.instance == instance => last.points.push(point),
+                _ => series.push(HistorySeries {
+                    metric: name.clone(),
+                    instance,
+                    resolution: resolution.as_str().to_string(),
+                    points: vec![point],
+                }),
+            }
+        }
+        Ok(series)
+    }
+
+    /// Every series that has been recorded
+    pub fn series(&self) -> anyhow::Result<Vec<HistorySeriesInfo>> {
+        let state = self.state.lock().unwrap();
+        let mut stmt = state
+            .conn
+            .prepare_cached("SELECT metric, instance FROM series ORDER BY metric, instance")?;
+        let series = stmt
+            .query_map([], |row| {
+                let instance: String = row.get(1)?;
+                Ok(HistorySeriesInfo {
+                    metric: row.get(0)?,
+                    instance: (!instance.is_empty()).then_some(instance),
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(series)
+    }
+}
+
+/// ID of the series, created on first use
+fn series_id(conn: &Connection, metric: &str, instance: &str) -> rusqlite::Result<i64> {
+    let existing = conn
+        .query_row(
+            "SELECT id FROM series WHERE metric = ?1 AND instance = ?2",
+            params![metric, instance],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match existing {
+        Some(id) => Ok(id),
+        None => {
+            conn.execute("INSERT INTO series (metric, instance) VALUES (?1, ?2)", params![metric, instance])?;
+            Ok(conn.last_insert_rowid())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Aligned to every tier so buckets start at `T0`
+    const T0: i64 = 1_700_000_400;
+
+    fn store(dir: &Path) -> HistoryStore {
+        let metrics = vec!["cpu.global_usage".to_string(), "disk.*.used_pct".to_string()];
+        HistoryStore::open(&dir.join("history.db"), metrics).unwrap()
+    }
+
+    fn sample(cpu: f64) -> serde_json::Value {
+        json!({
+            "cpu": {"global_usage": cpu},
+            "disks": [
+                {"mount_point": "/", "used_pct": 40.0},
+                {"mount_point": "/var", "used_pct": 70.0},
+            ],
+        })
+    }
+
+    fn time(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, 0).unwrap()
+    }
+
+    /// `(offset from T0, average, min, max)` of each stored point
+    fn points(store: &HistoryStore, metric: &str, resolution: Resolution) -> Vec<(i64, f64, f64, f64)> {
+        let series = store.query(metric, time(0), time(T0 + 100 * 86400), Some(resolution)).unwrap();
+        series
+            .iter()
+            .flat_map(|series| &series.points)
+            .map(|point| (point.timestamp.timestamp() - T0, point.value, point.min, point.max))
+            .collect()
+    }
+
+    #[test]
+    fn rolls_samples_up_into_minute_and_ten_minute_averages() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path());
+        // One sample a second for two minutes, the CPU usage counting up from 0
+        for i in 0..120 {
+            store.write(&sample(i as f64), T0 + i).unwrap();
+        }
+
+        let raw = points(&store, "cpu.global_usage", Resolution::Raw);
+        assert_eq!(raw.len(), 120);
+        assert_eq!(raw[5], (5, 5.0, 5.0, 5.0));
+        assert_eq!(
+            points(&store, "cpu.global_usage", Resolution::Minute),
+            [(0, 29.5, 0.0, 59.0), (60, 89.5, 60.0, 119.0)]
+        );
+        assert_eq!(
+            points(&store, "cpu.global_usage", Resolution::TenMinutes),
+            [(0, 59.5, 0.0, 119.0)]
+        );
+
+        let disks = store.query("disk.*.used_pct", time(T0), time(T0 + 600), Some(Resolution::TenMinutes)).unwrap();
+        let disks: Vec<_> = disks.iter().map(|s| (s.instance.as_deref(), s.points[0].value)).collect();
+        assert_eq!(disks, [(Some("/"), 40.0), (Some("/var"), 70.0)]);
+        let only_var = store.query("disk./var.used_pct", time(T0), time(T0 + 600), Some(Resolution::Minute)).unwrap();
+        assert_eq!(only_var.len(), 1);
+
+        let series: Vec<_> = store.series().unwrap().into_iter().map(|s| (s.metric, s.instance)).collect();
+        assert_eq!(
+            series,
+            [
+                ("cpu.global_usage".to_string(), None),
+                ("disk.*.used_pct".to_string(), Some("/".to_string())),
+                ("disk.*.used_pct".to_string(), Some("/var".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn prunes_each_tier_after_its_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path());
+        for i in 0..120 {
+            store.write(&sample(1.0), T0 + i).unwrap();
+        }
+
+        // Past the raw retention only the rollups of the first two minutes are left
+        let later = T0 + 3600 + 600;
+        store.write(&sample(2.0), later).unwrap();
+        let raw = points(&store, "cpu.global_usage", Resolution::Raw);
+        assert_eq!(raw.iter().map(|p| p.0).collect::<Vec<_>>(), [later - T0]);
+        assert_eq!(points(&store, "cpu.global_usage", Resolution::Minute).len(), 3);
+
+        // A day on, the minute averages of the first two minutes go too
+        let much_later = T0 + 86400 + 1200;
+        store.write(&sample(3.0), much_later).unwrap();
+        let minutes = points(&store, "cpu.global_usage", Resolution::Minute);
+        assert_eq!(minutes.iter().map(|p| p.0).collect::<Vec<_>>(), [later - T0, much_later - T0]);
+        assert_eq!(points(&store, "cpu.global_usage", Resolution::TenMinutes).len(), 3);
+    }
+
+    #[test]
+    fn keeps_history_across_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        store(dir.path()).write(&sample(10.0), T0).unwrap();
+        let reopened = store(dir.path());
+        reopened.write(&sample(20.0), T0 + 1).unwrap();
+        assert_eq!(points(&reopened, "cpu.global_usage", Resolution::Minute), [(0, 15.0, 10.0, 20.0)]);
+    }
+}
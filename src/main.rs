@@ -1,33 +1,64 @@
-use axum::{Router, extract::State, middleware, response::Json, routing::get};
+use arc_swap::ArcSwap;
+use axum::{
+    Extension, Router,
+    extract::{Path, Query, State, rejection::JsonRejection},
+    http::HeaderMap,
+    middleware,
+    response::{IntoResponse, Json, Response, sse::{Event, KeepAlive, Sse}},
+    routing::{get, post},
+};
+use futures_util::{StreamExt as _, stream};
+use hyper::{Request, body::Incoming};
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tower::ServiceBuilder;
+use tokio::sync::watch;
+use tower::{ServiceBuilder, ServiceExt as _};
 use tower_governor::{GovernorLayer, governor::GovernorConfigBuilder};
 use tower_http::trace::TraceLayer;
-use hyper_util::{
-    rt::{TokioExecutor, TokioIo},
-    server::conn::auto::Builder,
-    service::TowerToHyperService,
+use rmcp::{
+    ServiceExt,
+    transport::{
+        stdio,
+        streamable_http_server::{
+            StreamableHttpService,
+            session::{SessionManager, local::LocalSessionManager},
+        },
+    },
 };
-use rmcp::{ServiceExt, transport::{stdio, streamable_http_server::{StreamableHttpService, session::local::LocalSessionManager}}};
-use tracing_subscriber::{self, EnvFilter};
+use tracing_subscriber::{self, EnvFilter, prelude::*};
 
+mod alerts;
 mod api;
 mod cfg;
+mod containers;
+mod control;
+mod events;
+mod forecast;
+mod history;
 mod macros;
 mod mcp;
+mod notify;
 mod middlewares;
 mod models;
+mod procfs;
+mod reload;
+mod sampler;
+mod server;
 mod service;
+mod shutdown;
+mod systemd;
 
 use api::Resp;
 use service::AppState;
 use mcp::SysInfoMcp;
 use cfg::McpMode;
+use shutdown::Shutdown;
 
 #[derive(Clone)]
 struct AuthState {
     expected_credentials: String,
+    admin_credentials: Option<String>,
+    trusted_uids: Vec<u32>,
 }
 
 // Handler functions
@@ -54,22 +85,414 @@ async fn get_cpu_info(State(app_state): State<Arc<AppState>>) -> Json<Resp<model
     }
 }
 
-async fn get_memory_info(State(app_state): State<Arc<AppState>>) -> Json<Resp<models::MemoryInfo>> {
+#[derive(serde::Deserialize)]
+struct MemoryQuery {
+    detail: Option<String>,
+}
+
+async fn get_memory_info(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<MemoryQuery>,
+) -> Json<Resp<models::MemoryInfo>> {
+    let detail = match query.detail.as_deref() {
+        None | Some("basic") => false,
+        Some("full") => true,
+        Some(other) => return Json(Resp::error(400, format!("unknown detail level {:?}, expected basic or full", other))),
+    };
+    match app_state.get_memory(detail) {
+        Ok(memory) => Json(Resp::success(memory)),
+        Err(e) => Json(Resp::error(500, format!("{}", e))),
+    }
+}
+
+async fn get_disk_io_info(State(app_state): State<Arc<AppState>>) -> Json<Resp<Vec<models::DiskIoInfo>>> {
     match app_state.get_system_info() {
-        Ok(info) => Json(Resp::success(info.memory)),
+        Ok(info) => Json(Resp::success(info.disk_io)),
         Err(e) => Json(Resp::error(500, format!("{}", e))),
     }
 }
 
+async fn get_network_info(
+    State(app_state): State<Arc<AppState>>,
+) -> Json<Resp<std::collections::HashMap<String, models::NetworkInfo>>> {
+    match app_state.get_system_info() {
+        Ok(info) => Json(Resp::success(info.networks)),
+        Err(e) => Json(Resp::error(500, format!("{}", e))),
+    }
+}
+
+async fn get_tcp_info(State(app_state): State<Arc<AppState>>) -> Json<Resp<Option<models::TcpStats>>> {
+    match app_state.get_system_info() {
+        Ok(info) => Json(Resp::success(info.tcp)),
+        Err(e) => Json(Resp::error(500, format!("{}", e))),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SocketQuery {
+    port: Option<u16>,
+    state: Option<String>,
+    pid: Option<u32>,
+}
+
+async fn get_socket_info(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<SocketQuery>,
+) -> Json<Resp<Vec<models::SocketInfo>>> {
+    let filter = service::SocketFilter {
+        port: query.port,
+        state: query.state,
+        pid: query.pid,
+    };
+    match service::run_blocking(move || app_state.get_sockets(&filter)).await {
+        Ok(sockets) => Json(Resp::success(sockets)),
+        Err(e) => Json(Resp::error(api::error_code(&e), format!("{}", e))),
+    }
+}
+
+async fn get_container_info(State(app_state): State<Arc<AppState>>) -> Json<Resp<Vec<models::ContainerInfo>>> {
+    match app_state.get_system_info() {
+        Ok(info) => Json(Resp::success(info.containers)),
+        Err(e) => Json(Resp::error(500, format!("{}", e))),
+    }
+}
+
+async fn get_user_info(State(app_state): State<Arc<AppState>>) -> Json<Resp<models::UsersReport>> {
+    match app_state.get_users() {
+        Ok(report) => Json(Resp::success(report)),
+        Err(e) => Json(Resp::error(500, format!("{}", e))),
+    }
+}
+
+async fn get_component_info(State(app_state): State<Arc<AppState>>) -> Json<Resp<Vec<models::ComponentInfo>>> {
+    match app_state.get_system_info() {
+        Ok(info) => Json(Resp::success(info.components)),
+        Err(e) => Json(Resp::error(500, format!("{}", e))),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct PressureQuery {
+    cgroup: Option<String>,
+}
+
+async fn get_pressure_info(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<PressureQuery>,
+) -> Json<Resp<models::PressureReport>> {
+    match app_state.get_pressure(query.cgroup.as_deref()) {
+        Ok(report) => Json(Resp::success(report)),
+        Err(e) => Json(Resp::error(api::error_code(&e), format!("{}", e))),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ProcessQuery {
+    group_by: Option<String>,
+}
+
 async fn get_process_info(
     State(app_state): State<Arc<AppState>>,
+    Query(query): Query<ProcessQuery>,
 ) -> Json<Resp<models::ProcessSummary>> {
-    match app_state.get_system_info() {
-        Ok(info) => Json(Resp::success(info.processes)),
+    let group_by = match query.group_by.as_deref().map(str::parse).transpose() {
+        Ok(group_by) => group_by,
+        Err(e) => return Json(Resp::error(400, format!("{}", e))),
+    };
+    match service::run_blocking(move || app_state.get_processes(group_by, 10)).await {
+        Ok(summary) => Json(Resp::success(summary)),
         Err(e) => Json(Resp::error(500, format!("{}", e))),
     }
 }
 
+async fn get_process_detail(
+    State(app_state): State<Arc<AppState>>,
+    Path(pid): Path<u32>,
+) -> Json<Resp<models::ProcessDetail>> {
+    match app_state.get_process(pid) {
+        Ok(detail) => Json(Resp::success(detail)),
+        Err(e) => Json(Resp::error(api::error_code(&e), format!("{}", e))),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct AlertQuery {
+    state: Option<String>,
+}
+
+async fn get_alerts(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<AlertQuery>,
+) -> Json<Resp<Vec<models::Alert>>> {
+    let state = match query.state.as_deref() {
+        None | Some("active") => None,
+        Some("pending") => Some(models::AlertState::Pending),
+        Some("firing") => Some(models::AlertState::Firing),
+        Some("resolved") => Some(models::AlertState::Resolved),
+        Some(other) => {
+            return Json(Resp::error(
+                400,
+                format!("unknown alert state {:?}, expected active, pending, firing or resolved", other),
+            ));
+        }
+    };
+    let alerts = app_state
+        .get_alerts(state == Some(models::AlertState::Resolved))
+        .into_iter()
+        .filter(|alert| state.is_none_or(|state| alert.state == state))
+        .collect();
+    Json(Resp::success(alerts))
+}
+
+async fn get_alert_rules(State(app_state): State<Arc<AppState>>) -> Json<Resp<Vec<models::AlertRuleInfo>>> {
+    Json(Resp::success(app_state.get_alert_rules()))
+}
+
+#[derive(serde::Deserialize)]
+struct HistoryQuery {
+    metric: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    resolution: Option<String>,
+}
+
+async fn get_history(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<HistoryQuery>,
+) -> Json<Resp<Vec<models::HistorySeries>>> {
+    let Some(metric) = query.metric.as_deref().filter(|metric| !metric.is_empty()) else {
+        return Json(Resp::error(400, "metric is required, e.g. metric=disk.*.used_pct".to_string()));
+    };
+    let (from, to, resolution) = match parse_history_range(&query) {
+        Ok(parsed) => parsed,
+        Err(e) => return Json(Resp::error(400, format!("{}", e))),
+    };
+    let metric = metric.to_string();
+    match service::run_blocking(move || app_state.get_history(&metric, from, to, resolution)).await {
+        Ok(series) => Json(Resp::success(series)),
+        Err(e) => Json(Resp::error(api::error_code(&e), format!("{}", e))),
+    }
+}
+
+/// `from` defaults to an hour ago, `to` to now, and the resolution to the finest one covering `from`
+fn parse_history_range(
+    query: &HistoryQuery,
+) -> std::io::Result<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>, Option<history::Resolution>)> {
+    let now = chrono::Utc::now();
+    let from = query.from.as_deref().map(|s| history::parse_time(s, now)).transpose()?;
+    let to = query.to.as_deref().map(|s| history::parse_time(s, now)).transpose()?;
+    let resolution = match query.resolution.as_deref() {
+        None | Some("auto") => None,
+        Some(s) => Some(s.parse()?),
+    };
+    Ok((from.unwrap_or(now - chrono::Duration::hours(1)), to.unwrap_or(now), resolution))
+}
+
+async fn get_history_series(State(app_state): State<Arc<AppState>>) -> Json<Resp<Vec<models::HistorySeriesInfo>>> {
+    match service::run_blocking(move || app_state.get_history_series()).await {
+        Ok(series) => Json(Resp::success(series)),
+        Err(e) => Json(Resp::error(api::error_code(&e), format!("{}", e))),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ForecastQuery {
+    resource: Option<String>,
+    window: Option<String>,
+    threshold: Option<f64>,
+}
+
+async fn get_forecast(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<ForecastQuery>,
+) -> Json<Resp<Vec<models::Forecast>>> {
+    let resource = match query.resource.as_deref().map(str::parse).transpose() {
+        Ok(resource) => resource,
+        Err(e) => return Json(Resp::error(400, format!("{}", e))),
+    };
+    let window = query.window.as_deref().unwrap_or("6h");
+    let Some(window) = alerts::parse_duration(window).filter(|window| !window.is_zero()) else {
+        return Json(Resp::error(400, format!("invalid window {:?}, expected a duration such as 6h or 7d", window)));
+    };
+    let threshold = query.threshold.unwrap_or(100.0);
+    match service::run_blocking(move || app_state.get_forecast(resource, window, threshold)).await {
+        Ok(forecasts) => Json(Resp::success(forecasts)),
+        Err(e) => Json(Resp::error(api::error_code(&e), format!("{}", e))),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ListQuery {
+    limit: Option<usize>,
+}
+
+async fn get_process_files(
+    State(app_state): State<Arc<AppState>>,
+    Path(pid): Path<u32>,
+    Query(query): Query<ListQuery>,
+) -> Json<Resp<models::ProcessFiles>> {
+    let limit = query.limit.unwrap_or(service::DEFAULT_FILES_LIMIT).min(service::MAX_LIST_LIMIT);
+    match service::run_blocking(move || app_state.get_process_files(pid, limit)).await {
+        Ok(files) => Json(Resp::success(files)),
+        Err(e) => Json(Resp::error(api::error_code(&e), format!("{}", e))),
+    }
+}
+
+async fn get_process_maps(
+    State(app_state): State<Arc<AppState>>,
+    Path(pid): Path<u32>,
+    Query(query): Query<ListQuery>,
+) -> Json<Resp<models::ProcessMaps>> {
+    let limit = query.limit.unwrap_or(service::DEFAULT_MAPS_LIMIT).min(service::MAX_LIST_LIMIT);
+    match service::run_blocking(move || app_state.get_process_maps(pid, limit)).await {
+        Ok(maps) => Json(Resp::success(maps)),
+        Err(e) => Json(Resp::error(api::error_code(&e), format!("{}", e))),
+    }
+}
+
+async fn get_process_threads(
+    State(app_state): State<Arc<AppState>>,
+    Path(pid): Path<u32>,
+    Query(query): Query<ListQuery>,
+) -> Json<Resp<models::ProcessThreads>> {
+    let limit = query.limit.unwrap_or(service::DEFAULT_THREADS_LIMIT).min(service::MAX_LIST_LIMIT);
+    match service::run_blocking(move || app_state.get_process_threads(pid, limit)).await {
+        Ok(threads) => Json(Resp::success(threads)),
+        Err(e) => Json(Resp::error(api::error_code(&e), format!("{}", e))),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SignalBody {
+    signal: String,
+}
+
+#[derive(serde::Deserialize)]
+struct ReniceBody {
+    nice: i32,
+}
+
+/// Handle a process control request, which needs the admin scope on top of regular auth
+fn control_process(
+    app_state: &AppState,
+    admin: Option<Extension<middlewares::AdminScope>>,
+    pid: u32,
+    action: Result<control::Action, String>,
+) -> Json<Resp<models::ProcessAction>> {
+    let Some(Extension(admin)) = admin else {
+        return Json(Resp::error(403, "process control requires the admin credentials".to_string()));
+    };
+    let action = match action {
+        Ok(action) => action,
+        Err(e) => return Json(Resp::error(400, e)),
+    };
+    match app_state.control_process(pid, action, &admin.actor) {
+        Ok(result) => Json(Resp::success(result)),
+        Err(e) => Json(Resp::error(api::error_code(&e), format!("{}", e))),
+    }
+}
+
+async fn signal_process(
+    State(app_state): State<Arc<AppState>>,
+    admin: Option<Extension<middlewares::AdminScope>>,
+    Path(pid): Path<u32>,
+    body: Result<Json<SignalBody>, JsonRejection>,
+) -> Json<Resp<models::ProcessAction>> {
+    let action = body
+        .map_err(|e| e.body_text())
+        .and_then(|Json(body)| body.signal.parse().map_err(|e| format!("{}", e)))
+        .map(control::Action::Signal);
+    control_process(&app_state, admin, pid, action)
+}
+
+async fn renice_process(
+    State(app_state): State<Arc<AppState>>,
+    admin: Option<Extension<middlewares::AdminScope>>,
+    Path(pid): Path<u32>,
+    body: Result<Json<ReniceBody>, JsonRejection>,
+) -> Json<Resp<models::ProcessAction>> {
+    let action = body.map(|Json(body)| control::Action::Renice(body.nice)).map_err(|e| e.body_text());
+    control_process(&app_state, admin, pid, action)
+}
+
+#[derive(serde::Deserialize)]
+struct CgroupQuery {
+    path: Option<String>,
+    depth: Option<usize>,
+}
+
+async fn get_cgroup_info(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<CgroupQuery>,
+) -> Json<Resp<models::CgroupInfo>> {
+    match service::run_blocking(move || app_state.get_cgroups(query.path.as_deref(), query.depth)).await {
+        Ok(tree) => Json(Resp::success(tree)),
+        Err(e) => Json(Resp::error(api::error_code(&e), format!("{}", e))),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct EventQuery {
+    since: Option<String>,
+    name: Option<String>,
+}
+
+async fn get_process_events(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<EventQuery>,
+) -> Json<Resp<Vec<models::ProcessEvent>>> {
+    match query.since.as_deref().map(str::parse).transpose() {
+        Ok(since) => Json(Resp::success(app_state.get_process_events(since, query.name.as_deref()))),
+        Err(e) => Json(Resp::error(400, format!("{}", e))),
+    }
+}
+
+/// Server-sent events as processes start and exit. Replays buffered events after
+/// `since` (or the `Last-Event-ID` a reconnecting client sends) before going live.
+async fn stream_process_events(
+    State(app_state): State<Arc<AppState>>,
+    Extension(shutdown): Extension<Shutdown>,
+    headers: HeaderMap,
+    Query(query): Query<EventQuery>,
+) -> Response {
+    let last_event_id = headers.get("last-event-id").and_then(|v| v.to_str().ok());
+    let since = match query.since.as_deref().or(last_event_id).map(str::parse).transpose() {
+        Ok(since) => since,
+        Err(e) => return Json(Resp::<()>::error(400, format!("{}", e))).into_response(),
+    };
+
+    // Subscribe before reading the buffer so nothing falls between the two
+    let rx = app_state.subscribe_process_events();
+    let backlog = match since {
+        Some(since) => app_state.get_process_events(Some(since), query.name.as_deref()),
+        None => Vec::new(),
+    };
+    let replayed = backlog.last().map_or(0, |event| event.id);
+
+    let live = stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => return Some((event, rx)),
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Process event stream fell behind, skipped {} event(s)", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+    .filter(move |event| std::future::ready(event.id > replayed));
+
+    let name = query.name;
+    let events = stream::iter(backlog)
+        .chain(live)
+        .filter(move |event| std::future::ready(name.as_ref().is_none_or(|name| &event.name == name)))
+        .map(|event| Event::default().id(event.id.to_string()).event(event.kind.as_str()).json_data(&event))
+        // Open streams would otherwise hold graceful shutdown until the drain timeout
+        .take_until(async move { shutdown.wait().await });
+
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
 async fn health_check() -> Json<Resp<serde_json::Value>> {
     use chrono::Utc;
     let health_data = serde_json::json!({
@@ -81,41 +504,86 @@ async fn health_check() -> Json<Resp<serde_json::Value>> {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    dotenv::dotenv().ok();
-    
-    // Initialize tracing subscriber
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
-        .with_writer(std::io::stderr)
+    let config_file = cfg::config_file();
+    // A missing or unreadable file leaves the environment alone, as before any file existed
+    let file_vars = cfg::read_file(&config_file).unwrap_or_default();
+    let config = cfg::Config::from_vars(&file_vars)?;
+
+    // Initialize tracing subscriber, keeping a handle so SIGHUP can swap the filter
+    let (log_filter, log_filter_handle) = tracing_subscriber::reload::Layer::new(
+        EnvFilter::try_new(&config.log_filter).unwrap_or_else(|_| EnvFilter::new("info")),
+    );
+    tracing_subscriber::registry()
+        .with(log_filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
         .init();
 
-    let config = cfg::Config::from_env();
-    let app_state = Arc::new(AppState::new());
+    let activated = systemd::take_listeners()?;
+    let history = config
+        .history_db
+        .as_deref()
+        .map(|path| history::HistoryStore::open(path, config.history_metrics.clone()))
+        .transpose()?;
+    let app_state = Arc::new(AppState::new(
+        config.host_paths.clone(),
+        config.mount_filter.clone(),
+        config.container_runtime_socket.clone(),
+        config.control_policy.clone(),
+        config.alert_rules.clone(),
+        config.webhooks.clone(),
+        history,
+    ));
+
+    let shutdown = Shutdown::new();
+    tokio::spawn(shutdown::listen_for_signals(shutdown.clone()));
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown.wait().await;
+            systemd::notify_stopping();
+        }
+    });
+
+    let (config_tx, config_rx) = watch::channel(config.clone());
+    tokio::spawn(reload::listen_for_reload(
+        config_file,
+        file_vars,
+        config_tx,
+        log_filter_handle,
+        shutdown.clone(),
+    ));
+    tokio::spawn(sampler::run(app_state.clone(), config_rx.clone(), shutdown.clone()));
+    tokio::spawn(systemd::run_watchdog(app_state.clone(), config_rx.clone(), shutdown.clone()));
 
     match config.mcp_mode {
         McpMode::Stdio => {
-            run_mcp_stdio(app_state).await
+            run_mcp_stdio(app_state, shutdown).await
         }
         McpMode::Http => {
-            run_mcp_http(app_state, config).await
+            run_mcp_http(app_state, config, activated.mcp, shutdown).await
         }
         McpMode::Both => {
-            let config_clone = config.clone();
-            let (rest_result, mcp_result) = tokio::try_join!(
-                tokio::spawn(run_rest_server(app_state.clone(), config.clone())),
-                tokio::spawn(run_mcp_http(app_state, config_clone)),
-            )?;
-            rest_result?;
-            mcp_result?;
+            let mut rest = tokio::spawn(run_rest_server(app_state.clone(), config_rx, activated.rest, shutdown.clone()));
+            let mut mcp = tokio::spawn(run_mcp_http(app_state, config, activated.mcp, shutdown.clone()));
+
+            // Whichever server stops first (signal or failure) takes the other one down with it
+            let (first, other) = tokio::select! {
+                res = &mut rest => (res, mcp),
+                res = &mut mcp => (res, rest),
+            };
+            shutdown.trigger();
+            let second = other.await;
+            first??;
+            second??;
             Ok(())
         }
         McpMode::RestOnly => {
-            run_rest_server(app_state, config).await
+            run_rest_server(app_state, config_rx, activated.rest, shutdown).await
         }
     }
 }
 
-async fn run_mcp_stdio(app_state: Arc<AppState>) -> anyhow::Result<()> {
+async fn run_mcp_stdio(app_state: Arc<AppState>, shutdown: Shutdown) -> anyhow::Result<()> {
     tracing::info!("Starting MCP server in stdio mode");
     
     let service = SysInfoMcp::new(app_state)
@@ -125,66 +593,157 @@ async fn run_mcp_stdio(app_state: Arc<AppState>) -> anyhow::Result<()> {
             tracing::error!("MCP stdio serving error: {:?}", e);
         })?;
 
-    service.waiting().await?;
+    let cancel = service.cancellation_token();
+    tokio::select! {
+        res = service.waiting() => {
+            res?;
+        }
+        _ = shutdown.wait() => {
+            cancel.cancel();
+        }
+    }
     Ok(())
 }
 
-async fn run_mcp_http(app_state: Arc<AppState>, config: cfg::Config) -> anyhow::Result<()> {
-    tracing::info!("Starting MCP server in HTTP mode on port {}", config.mcp_port);
+async fn run_mcp_http(
+    app_state: Arc<AppState>,
+    config: cfg::Config,
+    activated: Vec<server::Listener>,
+    shutdown: Shutdown,
+) -> anyhow::Result<()> {
+    tracing::info!("Starting MCP server in HTTP mode");
     
-    let service = TowerToHyperService::new(StreamableHttpService::new(
+    let session_manager = Arc::new(LocalSessionManager::default());
+    let service = StreamableHttpService::new(
         {
             let app_state = app_state.clone();
             move || Ok(SysInfoMcp::new(app_state.clone()))
         },
-        LocalSessionManager::default().into(),
+        session_manager.clone(),
         Default::default(),
-    ));
+    );
 
-    let mcp_addr = format!("{}:{}", config.server_host, config.mcp_port);
-    let listener = tokio::net::TcpListener::bind(&mcp_addr).await?;
-    
-    loop {
-        let io = tokio::select! {
-            _ = tokio::signal::ctrl_c() => break,
-            accept = listener.accept() => {
-                TokioIo::new(accept?.0)
-            }
-        };
-        let service = service.clone();
-        tokio::spawn(async move {
-            let _result = Builder::new(TokioExecutor::default())
-                .serve_connection(io, service)
-                .await;
-        });
+    // Open SSE streams only end when their session does, so close every
+    // session up front instead of waiting for the drain timeout
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown.wait().await;
+            close_mcp_sessions(&session_manager).await;
+        }
+    });
+
+    // MCP has no basic auth, so over a Unix socket only trusted UIDs get in
+    let app = Router::new()
+        .fallback_service(service)
+        .layer(middleware::from_fn_with_state(
+            Arc::new(config.socket_trusted_uids.clone()),
+            middlewares::peer_uid_filter,
+        ));
+
+    let listeners = bind_listeners(&config, config.mcp_port, config.mcp_socket.as_deref(), activated).await?;
+    server::serve("MCP", listeners, app, shutdown, config.shutdown_timeout).await
+}
+
+async fn close_mcp_sessions(session_manager: &LocalSessionManager) {
+    let ids: Vec<_> = session_manager.sessions.read().await.keys().cloned().collect();
+    if ids.is_empty() {
+        return;
+    }
+    tracing::info!("Closing {} MCP session(s)", ids.len());
+    for id in ids {
+        if let Err(e) = session_manager.close_session(&id).await {
+            tracing::warn!("Failed to close MCP session {}: {}", id, e);
+        }
     }
-    Ok(())
 }
 
-async fn run_rest_server(app_state: Arc<AppState>, config: cfg::Config) -> anyhow::Result<()> {
-    // Pre-compute auth credentials for performance
-    let auth_state = AuthState {
-        expected_credentials: format!("{}:{}", config.username, config.password),
-    };
+async fn run_rest_server(
+    app_state: Arc<AppState>,
+    mut config_rx: watch::Receiver<cfg::Config>,
+    activated: Vec<server::Listener>,
+    shutdown: Shutdown,
+) -> anyhow::Result<()> {
+    let config = config_rx.borrow_and_update().clone();
 
-    tracing::info!(
-        "Starting REST API server on http://{}:{}",
-        config.server_host,
-        config.server_port
-    );
+    tracing::info!("Starting REST API server");
     tracing::info!(
         "Using username: {}, password: {}",
         config.username,
         config.password
     );
 
+    // Requests are dispatched to whichever router is current, so a reload
+    // swaps credentials and rate limits together without dropping connections
+    let router = Arc::new(ArcSwap::from_pointee(build_router(app_state.clone(), &config, &shutdown)?));
+    tokio::spawn({
+        let router = router.clone();
+        let shutdown = shutdown.clone();
+        async move {
+            while config_rx.changed().await.is_ok() {
+                let config = config_rx.borrow_and_update().clone();
+                match build_router(app_state.clone(), &config, &shutdown) {
+                    Ok(new_router) => {
+                        router.store(Arc::new(new_router));
+                        tracing::info!(
+                            "REST auth and rate limit reloaded (username: {}, rate limit: {}/min)",
+                            config.username,
+                            config.rate_limit
+                        );
+                    }
+                    Err(e) => tracing::error!("Failed to rebuild REST router, keeping previous one: {}", e),
+                }
+            }
+        }
+    });
+    let app = tower::service_fn(move |req: Request<Incoming>| {
+        let router = Router::clone(&router.load());
+        router.oneshot(req)
+    });
+
+    // Start server
+    let listeners = bind_listeners(&config, config.server_port, config.server_socket.as_deref(), activated).await?;
+    server::serve("REST", listeners, app, shutdown, config.shutdown_timeout).await
+}
+
+/// Use the sockets systemd handed us if there are any, otherwise bind the configured ones
+async fn bind_listeners(
+    config: &cfg::Config,
+    port: u16,
+    socket: Option<&std::path::Path>,
+    activated: Vec<server::Listener>,
+) -> anyhow::Result<Vec<server::Listener>> {
+    if !activated.is_empty() {
+        return Ok(activated);
+    }
+
+    let mut listeners = Vec::new();
+    if !config.disable_tcp {
+        let addr = SocketAddr::from((config.server_host.parse::<std::net::IpAddr>()?, port));
+        listeners.push(server::Listener::bind_tcp(addr).await?);
+    }
+    if let Some(path) = socket {
+        listeners.push(server::Listener::bind_unix(path, config.socket_mode, config.socket_owner)?);
+    }
+    Ok(listeners)
+}
+
+fn build_router(app_state: Arc<AppState>, config: &cfg::Config, shutdown: &Shutdown) -> anyhow::Result<Router> {
+    // Pre-compute auth credentials for performance
+    let auth_state = AuthState {
+        expected_credentials: format!("{}:{}", config.username, config.password),
+        admin_credentials: config.admin_credentials.clone(),
+        trusted_uids: config.socket_trusted_uids.clone(),
+    };
+
     // Create rate limiting governor
     let governor_conf = Arc::new(
         GovernorConfigBuilder::default()
-            .per_second(config.rate_limit as u64 / 60)
-            .burst_size(config.rate_limit as u32)
+            .key_extractor(middlewares::PeerKeyExtractor)
+            .per_second((config.rate_limit as u64 / 60).max(1))
+            .burst_size((config.rate_limit as u32).max(1))
             .finish()
-            .unwrap(),
+            .ok_or_else(|| anyhow::anyhow!("invalid rate limit {}", config.rate_limit))?,
     );
 
     // Build the application
@@ -194,7 +753,29 @@ async fn run_rest_server(app_state: Arc<AppState>, config: cfg::Config) -> anyho
         .route("/api/v1/system/overview", get(get_system_overview))
         .route("/api/v1/system/cpu", get(get_cpu_info))
         .route("/api/v1/system/memory", get(get_memory_info))
+        .route("/api/v1/system/diskio", get(get_disk_io_info))
+        .route("/api/v1/system/networks", get(get_network_info))
+        .route("/api/v1/system/networks/tcp", get(get_tcp_info))
+        .route("/api/v1/system/sockets", get(get_socket_info))
+        .route("/api/v1/system/components", get(get_component_info))
+        .route("/api/v1/system/pressure", get(get_pressure_info))
         .route("/api/v1/system/processes", get(get_process_info))
+        .route("/api/v1/processes/:pid", get(get_process_detail))
+        .route("/api/v1/processes/:pid/fds", get(get_process_files))
+        .route("/api/v1/processes/:pid/maps", get(get_process_maps))
+        .route("/api/v1/processes/:pid/threads", get(get_process_threads))
+        .route("/api/v1/processes/:pid/signal", post(signal_process))
+        .route("/api/v1/processes/:pid/renice", post(renice_process))
+        .route("/api/v1/cgroups", get(get_cgroup_info))
+        .route("/api/v1/containers", get(get_container_info))
+        .route("/api/v1/users", get(get_user_info))
+        .route("/api/v1/alerts", get(get_alerts))
+        .route("/api/v1/alerts/rules", get(get_alert_rules))
+        .route("/api/v1/history", get(get_history))
+        .route("/api/v1/history/series", get(get_history_series))
+        .route("/api/v1/forecast", get(get_forecast))
+        .route("/api/v1/events/processes", get(get_process_events))
+        .route("/api/v1/events/processes/stream", get(stream_process_events))
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
@@ -202,22 +783,13 @@ async fn run_rest_server(app_state: Arc<AppState>, config: cfg::Config) -> anyho
                     config: governor_conf,
                 })
                 .layer(middleware::from_fn_with_state(
-                    auth_state.clone(),
+                    auth_state,
                     middlewares::basic_auth,
                 ))
                 .into_inner(),
         )
+        .layer(Extension(shutdown.clone()))
         .with_state(app_state);
 
-    // Create socket address
-    let addr = SocketAddr::from((
-        config.server_host.parse::<std::net::IpAddr>()?,
-        config.server_port,
-    ));
-
-    // Start server
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
-
-    Ok(())
+    Ok(app)
 }
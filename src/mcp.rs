@@ -10,14 +10,107 @@ use rmcp::{
 };
 use serde_json::json;
 
-use crate::service::AppState;
+use crate::alerts;
+use crate::control;
+use crate::service::{
+    AppState, DEFAULT_FILES_LIMIT, DEFAULT_MAPS_LIMIT, DEFAULT_THREADS_LIMIT, MAX_LIST_LIMIT, SocketFilter, run_blocking,
+};
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct ProcessListRequest {
     /// Number of top processes to return (default: 10, max: 50)
     pub limit: Option<usize>,
-    /// Sort by 'cpu' or 'memory' (default: cpu)
+    /// 'cpu' or 'memory'; kept for compatibility, both rankings are always returned
+    #[allow(dead_code)]
     pub sort_by: Option<String>,
+    /// Also sum usage per 'name', 'exe', 'user' or 'cgroup' so many small copies of one program show up as one entry
+    pub group_by: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct MemoryRequest {
+    /// 'basic', or 'full' to add buffers, cache, slab, dirty pages, huge pages, commit and VM counters (default: basic)
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SocketRequest {
+    /// Only sockets whose local or remote port matches
+    pub port: Option<u16>,
+    /// Only sockets in this state, e.g. 'listen', 'established' or 'unconn' for UDP
+    pub state: Option<String>,
+    /// Only sockets held by this process
+    pub pid: Option<u32>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ProcessRequest {
+    /// Process ID
+    pub pid: u32,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CgroupRequest {
+    /// cgroup v2 path to start from (default: '/', the whole hierarchy)
+    pub path: Option<String>,
+    /// How many levels of children to include (default: all)
+    pub depth: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct PressureRequest {
+    /// Optional cgroup v2 path (e.g. '/system.slice/nginx.service') to include its pressure too
+    pub cgroup: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ProcessResourceRequest {
+    /// Process ID
+    pub pid: u32,
+    /// 'fds' (open files), 'maps' (memory mappings) or 'threads'
+    pub resource: String,
+    /// Maximum number of entries to list (default: 100 for fds and threads, 20 for maps; max: 1000)
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct AlertRequest {
+    /// Also include recently resolved alerts (default: false)
+    pub include_resolved: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SignalRequest {
+    /// Process ID
+    pub pid: u32,
+    /// One of TERM, KILL, STOP, CONT or HUP
+    pub signal: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ReniceRequest {
+    /// Process ID
+    pub pid: u32,
+    /// New nice value from -20 (highest priority) to 19 (lowest)
+    pub nice: i32,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ProcessEventRequest {
+    /// Only events after this event ID or RFC 3339 timestamp (default: every buffered event)
+    pub since: Option<String>,
+    /// Only events for processes with this exact name
+    pub name: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ForecastRequest {
+    /// Only forecast 'disk', 'memory' or 'swap' (default: all)
+    pub resource: Option<String>,
+    /// How much history to fit, e.g. '1h', '6h' or '7d' (default: 6h)
+    pub window: Option<String>,
+    /// Usage percentage to forecast reaching (default: 100)
+    pub threshold: Option<f64>,
 }
 
 #[derive(Clone)]
@@ -35,6 +128,24 @@ impl SysInfoMcp {
         }
     }
 
+    /// MCP callers carry no credentials, so the admin scope here is the server opting in
+    fn control_process(&self, pid: u32, action: control::Action) -> Result<CallToolResult, McpError> {
+        if !self.app_state.control_policy().mcp {
+            return Err(McpError::invalid_request(
+                "process control over MCP is disabled; set PROCESS_CONTROL_MCP=true on the server to allow it",
+                None,
+            ));
+        }
+        match self.app_state.control_process(pid, action, "mcp") {
+            Ok(result) => {
+                let json_str = serde_json::to_string_pretty(&result)
+                    .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to apply {} to process {}: {}", action, pid, e), None)),
+        }
+    }
+
     #[tool(description = "Get complete system information including CPU, memory, and processes")]
     async fn get_system_info(&self) -> Result<CallToolResult, McpError> {
         match self.app_state.get_system_info() {
@@ -71,35 +182,275 @@ impl SysInfoMcp {
         }
     }
 
-    #[tool(description = "Get memory information including RAM and swap usage")]
-    async fn get_memory_info(&self) -> Result<CallToolResult, McpError> {
+    #[tool(description = "Get memory information including RAM and swap usage, optionally with a detailed kernel breakdown")]
+    async fn get_memory_info(&self, Parameters(req): Parameters<MemoryRequest>) -> Result<CallToolResult, McpError> {
+        let detail = match req.detail.as_deref() {
+            None | Some("basic") => false,
+            Some("full") => true,
+            Some(other) => {
+                return Err(McpError::invalid_params(
+                    format!("unknown detail level {:?}, expected basic or full", other),
+                    None,
+                ));
+            }
+        };
+        match self.app_state.get_memory(detail) {
+            Ok(memory) => {
+                let json_str = serde_json::to_string_pretty(&memory)
+                    .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get memory info: {}", e), None)),
+        }
+    }
+
+    #[tool(description = "Get per-device disk I/O: throughput, IOPS, in-flight requests, average await and utilization over the last sample interval")]
+    async fn get_disk_io(&self) -> Result<CallToolResult, McpError> {
         match self.app_state.get_system_info() {
             Ok(info) => {
-                let json_str = serde_json::to_string_pretty(&info.memory)
+                let json_str = serde_json::to_string_pretty(&info.disk_io)
                     .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
                 Ok(CallToolResult::success(vec![Content::text(json_str)]))
             }
-            Err(e) => Err(McpError::internal_error(format!("Failed to get memory info: {}", e), None)),
+            Err(e) => Err(McpError::internal_error(format!("Failed to get disk I/O info: {}", e), None)),
         }
     }
 
-    #[tool(description = "Get process information with top CPU and memory consumers")]
-    async fn get_processes(&self, Parameters(req): Parameters<ProcessListRequest>) -> Result<CallToolResult, McpError> {
+    #[tool(description = "Get network interface counters and bytes/packets per second, plus host-wide TCP statistics such as retransmits, resets and listen drops")]
+    async fn get_network_info(&self) -> Result<CallToolResult, McpError> {
+        match self.app_state.get_system_info() {
+            Ok(info) => {
+                let json_str = serde_json::to_string_pretty(&json!({
+                    "interfaces": info.networks,
+                    "tcp": info.tcp,
+                }))
+                .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get network info: {}", e), None)),
+        }
+    }
+
+    #[tool(description = "List TCP and UDP sockets with local/remote address, state and owning process; use it to find out what is listening on a port")]
+    async fn get_sockets(&self, Parameters(req): Parameters<SocketRequest>) -> Result<CallToolResult, McpError> {
+        let filter = SocketFilter {
+            port: req.port,
+            state: req.state,
+            pid: req.pid,
+        };
+        let app_state = self.app_state.clone();
+        match run_blocking(move || app_state.get_sockets(&filter)).await {
+            Ok(sockets) => {
+                let json_str = serde_json::to_string_pretty(&sockets)
+                    .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get sockets: {}", e), None)),
+        }
+    }
+
+    #[tool(description = "Get details of one process: parent, command line, state, threads, memory, CPU time, start time and cgroup")]
+    async fn get_process(&self, Parameters(req): Parameters<ProcessRequest>) -> Result<CallToolResult, McpError> {
+        match self.app_state.get_process(req.pid) {
+            Ok(detail) => {
+                let json_str = serde_json::to_string_pretty(&detail)
+                    .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get process: {}", e), None)),
+        }
+    }
+
+    #[tool(description = "Get open file descriptors (count vs limit, per type), memory mappings (RSS/PSS/swap totals and per file) or threads (per-thread CPU time and lifetime-average CPU usage) of one process; parts that can't be read are reported in 'errors'")]
+    async fn get_process_resources(&self, Parameters(req): Parameters<ProcessResourceRequest>) -> Result<CallToolResult, McpError> {
+        let limit = |default: usize| req.limit.unwrap_or(default).min(MAX_LIST_LIMIT);
+        let (app_state, pid) = (self.app_state.clone(), req.pid);
+        let result = match req.resource.as_str() {
+            "fds" => {
+                let limit = limit(DEFAULT_FILES_LIMIT);
+                run_blocking(move || app_state.get_process_files(pid, limit).map(|r| serde_json::to_string_pretty(&r))).await
+            }
+            "maps" => {
+                let limit = limit(DEFAULT_MAPS_LIMIT);
+                run_blocking(move || app_state.get_process_maps(pid, limit).map(|r| serde_json::to_string_pretty(&r))).await
+            }
+            "threads" => {
+                let limit = limit(DEFAULT_THREADS_LIMIT);
+                run_blocking(move || app_state.get_process_threads(pid, limit).map(|r| serde_json::to_string_pretty(&r)))
+                    .await
+            }
+            other => {
+                return Err(McpError::invalid_params(
+                    format!("unknown resource {:?}, expected fds, maps or threads", other),
+                    None,
+                ));
+            }
+        };
+        match result {
+            Ok(json_str) => {
+                let json_str = json_str.map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get process {}: {}", req.resource, e), None)),
+        }
+    }
+
+    #[tool(
+        description = "Send TERM, KILL, STOP, CONT or HUP to a process. Only works when process control is enabled for MCP on the server, and only for processes its allow/deny lists permit; every attempt is audited",
+        annotations(destructive_hint = true, read_only_hint = false)
+    )]
+    async fn signal_process(&self, Parameters(req): Parameters<SignalRequest>) -> Result<CallToolResult, McpError> {
+        let signal = req.signal.parse().map_err(|e| McpError::invalid_params(format!("{}", e), None))?;
+        self.control_process(req.pid, control::Action::Signal(signal))
+    }
+
+    #[tool(
+        description = "Change the nice value (CPU priority) of a process. Only works when process control is enabled for MCP on the server, and only for processes its allow/deny lists permit; every attempt is audited",
+        annotations(destructive_hint = true, idempotent_hint = true, read_only_hint = false)
+    )]
+    async fn renice_process(&self, Parameters(req): Parameters<ReniceRequest>) -> Result<CallToolResult, McpError> {
+        self.control_process(req.pid, control::Action::Renice(req.nice))
+    }
+
+    #[tool(description = "Get the cgroup v2 tree with CPU usage and throttling, memory current/max, I/O and pid counts per cgroup (systemd slices, containers)")]
+    async fn get_cgroups(&self, Parameters(req): Parameters<CgroupRequest>) -> Result<CallToolResult, McpError> {
+        let app_state = self.app_state.clone();
+        match run_blocking(move || app_state.get_cgroups(req.path.as_deref(), req.depth)).await {
+            Ok(tree) => {
+                let json_str = serde_json::to_string_pretty(&tree)
+                    .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get cgroups: {}", e), None)),
+        }
+    }
+
+    #[tool(description = "Get running containers (Docker, containerd, CRI-O, Podman) found from process cgroups, with CPU and memory summed over each container's processes")]
+    async fn get_containers(&self) -> Result<CallToolResult, McpError> {
+        match self.app_state.get_system_info() {
+            Ok(info) => {
+                let json_str = serde_json::to_string_pretty(&info.containers)
+                    .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get containers: {}", e), None)),
+        }
+    }
+
+    #[tool(description = "Get the server's threshold alerts: which rules are firing or pending, on which instance (disk, interface, ...), with current value and threshold, plus the configured rules. An empty 'alerts' list means nothing is alerting")]
+    async fn get_alerts(&self, Parameters(req): Parameters<AlertRequest>) -> Result<CallToolResult, McpError> {
+        let result = json!({
+            "alerts": self.app_state.get_alerts(req.include_resolved.unwrap_or(false)),
+            "rules": self.app_state.get_alert_rules(),
+        });
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json_str)]))
+    }
+
+    #[tool(description = "Forecast when disks will fill up and memory or swap will run out, from a linear regression over the stored usage history. Each entry has a human-readable summary, the ETA in seconds with an earliest/latest range, the rate in percentage points per hour, and a high/medium/low confidence from the fit. Requires the server's metric history (HISTORY_DB)")]
+    async fn get_forecast(&self, Parameters(req): Parameters<ForecastRequest>) -> Result<CallToolResult, McpError> {
+        let resource = req
+            .resource
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(|e| McpError::invalid_params(format!("{}", e), None))?;
+        let window = req.window.as_deref().unwrap_or("6h");
+        let window = alerts::parse_duration(window).filter(|window| !window.is_zero()).ok_or_else(|| {
+            McpError::invalid_params(format!("invalid window {:?}, expected a duration such as 6h or 7d", window), None)
+        })?;
+        let app_state = self.app_state.clone();
+        let threshold = req.threshold.unwrap_or(100.0);
+        match run_blocking(move || app_state.get_forecast(resource, window, threshold)).await {
+            Ok(forecasts) => {
+                let json_str = serde_json::to_string_pretty(&forecasts)
+                    .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get forecast: {}", e), None)),
+        }
+    }
+
+    #[tool(description = "Get process health: process count per state, zombie processes with the parent that hasn't reaped them, and processes stuck in uninterruptible D-state sleep with their kernel wait channel and how long they've been blocked")]
+    async fn get_process_health(&self) -> Result<CallToolResult, McpError> {
+        match self.app_state.get_system_info() {
+            Ok(info) => {
+                let json_str = serde_json::to_string_pretty(&info.processes.health)
+                    .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get process health: {}", e), None)),
+        }
+    }
+
+    #[tool(description = "Get recent process start and exit events (pid, name, cmdline, runtime) detected between samples; useful to spot crash-looping services")]
+    async fn get_process_events(&self, Parameters(req): Parameters<ProcessEventRequest>) -> Result<CallToolResult, McpError> {
+        let since = req
+            .since
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(|e| McpError::invalid_params(format!("{}", e), None))?;
+        let events = self.app_state.get_process_events(since, req.name.as_deref());
+        let json_str = serde_json::to_string_pretty(&events)
+            .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json_str)]))
+    }
+
+    #[tool(description = "Get process count, CPU and memory summed per user account, plus logged-in sessions from utmp")]
+    async fn get_users(&self) -> Result<CallToolResult, McpError> {
+        match self.app_state.get_users() {
+            Ok(report) => {
+                let json_str = serde_json::to_string_pretty(&report)
+                    .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get users: {}", e), None)),
+        }
+    }
+
+    #[tool(description = "Get hardware temperature sensors (label, current, max and critical in Celsius); empty when the machine exposes none, as in most VMs")]
+    async fn get_components(&self) -> Result<CallToolResult, McpError> {
         match self.app_state.get_system_info() {
-            Ok(mut info) => {
-                let limit = req.limit.unwrap_or(10).min(50);
-                let sort_by = req.sort_by.unwrap_or_else(|| "cpu".to_string());
+            Ok(info) => {
+                let json_str = serde_json::to_string_pretty(&info.components)
+                    .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get component info: {}", e), None)),
+        }
+    }
 
+    #[tool(description = "Get Pressure Stall Information (PSI) for CPU, memory and I/O, showing how long tasks were stalled waiting on each resource")]
+    async fn get_pressure(&self, Parameters(req): Parameters<PressureRequest>) -> Result<CallToolResult, McpError> {
+        match self.app_state.get_pressure(req.cgroup.as_deref()) {
+            Ok(report) => {
+                let json_str = serde_json::to_string_pretty(&report)
+                    .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
+                Ok(CallToolResult::success(vec![Content::text(json_str)]))
+            }
+            Err(e) => Err(McpError::internal_error(format!("Failed to get pressure info: {}", e), None)),
+        }
+    }
+
+    #[tool(description = "Get process information with top CPU and memory consumers, optionally grouped by name, executable, user or cgroup")]
+    async fn get_processes(&self, Parameters(req): Parameters<ProcessListRequest>) -> Result<CallToolResult, McpError> {
+        let limit = req.limit.unwrap_or(10).min(50);
+        let group_by = req
+            .group_by
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(|e| McpError::invalid_params(format!("{}", e), None))?;
+        let app_state = self.app_state.clone();
+        match run_blocking(move || app_state.get_processes(group_by, limit)).await {
+            Ok(mut processes) => {
                 // Truncate process lists based on requested limit
-                if sort_by == "memory" {
-                    info.processes.top_cpu_processes.truncate(limit);
-                    info.processes.top_memory_processes.truncate(limit);
-                } else {
-                    info.processes.top_cpu_processes.truncate(limit);
-                    info.processes.top_memory_processes.truncate(limit);
-                }
-
-                let json_str = serde_json::to_string_pretty(&info.processes)
+                processes.top_cpu_processes.truncate(limit);
+                processes.top_memory_processes.truncate(limit);
+
+                let json_str = serde_json::to_string_pretty(&processes)
                     .map_err(|e| McpError::internal_error(format!("Serialization error: {}", e), None))?;
                 Ok(CallToolResult::success(vec![Content::text(json_str)]))
             }
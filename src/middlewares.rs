@@ -1,21 +1,39 @@
 use axum::{
-    extract::{Request, State},
+    extract::{ConnectInfo, Request, State},
     http::HeaderValue,
     middleware::Next,
     response::Response,
 };
 use base64::{Engine as _, engine::general_purpose};
 use hyper::{StatusCode, header};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 use subtle::ConstantTimeEq;
+use tower_governor::{GovernorError, key_extractor::KeyExtractor};
 
 use crate::AuthState;
+use crate::server::PeerCred;
+
+/// Added to requests authenticated with the admin credentials
+#[derive(Debug, Clone)]
+pub struct AdminScope {
+    /// Admin user name and where the request came from, for the audit log
+    pub actor: String,
+}
 
 // Basic auth middleware
 pub async fn basic_auth(
     State(auth_state): State<AuthState>,
-    req: Request,
+    mut req: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
+    // Local callers on a Unix socket may be trusted by their kernel-verified UID instead
+    if let Some(peer) = req.extensions().get::<PeerCred>()
+        && auth_state.trusted_uids.contains(&peer.uid)
+    {
+        return Ok(next.run(req).await);
+    }
+
     let auth_header = req
         .headers()
         .get(header::AUTHORIZATION)
@@ -42,6 +60,18 @@ pub async fn basic_auth(
     };
 
     // Use constant-time comparison to prevent timing attacks
+    if let Some(admin_credentials) = &auth_state.admin_credentials
+        && bool::from(credentials_str.as_bytes().ct_eq(admin_credentials.as_bytes()))
+    {
+        let username = credentials_str.split_once(':').map_or("", |(username, _)| username);
+        let actor = match (req.extensions().get::<ConnectInfo<SocketAddr>>(), req.extensions().get::<PeerCred>()) {
+            (Some(ConnectInfo(addr)), _) => format!("{} from {}", username, addr.ip()),
+            (None, Some(peer)) => format!("{} from uid {}", username, peer.uid),
+            (None, None) => username.to_string(),
+        };
+        req.extensions_mut().insert(AdminScope { actor });
+        return Ok(next.run(req).await);
+    }
     if credentials_str
         .as_bytes()
         .ct_eq(auth_state.expected_credentials.as_bytes())
@@ -62,3 +92,41 @@ fn create_auth_error_response() -> Result<Response, StatusCode> {
     );
     Ok(response)
 }
+
+// Restrict Unix socket callers to the trusted UIDs (when any are configured)
+pub async fn peer_uid_filter(
+    State(trusted_uids): State<Arc<Vec<u32>>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    match req.extensions().get::<PeerCred>() {
+        Some(peer) if !trusted_uids.is_empty() && !trusted_uids.contains(&peer.uid) => {
+            Err(StatusCode::FORBIDDEN)
+        }
+        _ => Ok(next.run(req).await),
+    }
+}
+
+/// Rate limiting key: the peer IP for TCP callers, the peer UID for Unix socket callers
+#[derive(Debug, Clone, Copy)]
+pub struct PeerKeyExtractor;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum PeerKey {
+    Ip(IpAddr),
+    Uid(u32),
+}
+
+impl KeyExtractor for PeerKeyExtractor {
+    type Key = PeerKey;
+
+    fn extract<T>(&self, req: &hyper::Request<T>) -> Result<Self::Key, GovernorError> {
+        if let Some(ConnectInfo(addr)) = req.extensions().get::<ConnectInfo<SocketAddr>>() {
+            return Ok(PeerKey::Ip(addr.ip()));
+        }
+        if let Some(peer) = req.extensions().get::<PeerCred>() {
+            return Ok(PeerKey::Uid(peer.uid));
+        }
+        Err(GovernorError::UnableToExtractKey)
+    }
+}
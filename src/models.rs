@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SystemInfo {
@@ -7,7 +8,16 @@ pub struct SystemInfo {
     pub system: SystemOverview,
     pub cpu: CpuInfo,
     pub memory: MemoryInfo,
+    pub disks: Vec<DiskInfo>,
+    pub disk_io: Vec<DiskIoInfo>,
+    pub pressure: Option<PressureInfo>,
+    pub components: Vec<ComponentInfo>,
+    pub networks: HashMap<String, NetworkInfo>,
+    /// Host-wide TCP counters, `None` if `/proc/net/snmp` is unreadable
+    pub tcp: Option<TcpStats>,
     pub processes: ProcessSummary,
+    pub containers: Vec<ContainerInfo>,
+    pub users: Vec<UserInfo>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -25,6 +35,9 @@ pub struct CpuInfo {
     pub global_usage: f32,
     pub cores: Vec<CpuCore>,
     pub physical_core_count: Option<usize>,
+    pub load_average: Option<LoadAverage>,
+    pub tasks: Option<TaskCounts>,
+    pub times: Option<CpuTimes>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -32,6 +45,35 @@ pub struct CpuCore {
     pub name: String,
     pub usage: f32,
     pub frequency: u64,
+    pub times: Option<CpuTimes>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LoadAverage {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TaskCounts {
+    pub runnable: u64,
+    pub blocked: u64,
+}
+
+/// Share of CPU time (percent) spent in each state since the previous sample
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CpuTimes {
+    pub user: f32,
+    pub nice: f32,
+    pub system: f32,
+    pub idle: f32,
+    pub iowait: f32,
+    pub irq: f32,
+    pub softirq: f32,
+    pub steal: f32,
+    pub guest: f32,
+    pub guest_nice: f32,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -43,6 +85,185 @@ pub struct MemoryInfo {
     pub swap_total: u64,
     pub swap_used: u64,
     pub swap_free: u64,
+    /// Kernel memory breakdown, only filled in when asked for with `detail=full`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<MemoryDetail>,
+}
+
+/// Extra fields from `/proc/meminfo` and `/proc/vmstat`, all sizes in bytes
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MemoryDetail {
+    pub buffers: u64,
+    pub cached: u64,
+    /// tmpfs and shared memory segments
+    pub shared: u64,
+    pub slab_reclaimable: u64,
+    pub slab_unreclaimable: u64,
+    pub dirty: u64,
+    pub writeback: u64,
+    /// Memory the kernel has promised to processes
+    pub committed_as: u64,
+    /// Limit for `committed_as` under strict overcommit
+    pub commit_limit: u64,
+    pub huge_pages: HugePages,
+    pub vm: VmCounters,
+}
+
+/// Page counts, except for `page_size`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HugePages {
+    pub total: u64,
+    pub free: u64,
+    pub reserved: u64,
+    pub surplus: u64,
+    /// Size of one huge page in bytes
+    pub page_size: u64,
+}
+
+/// Counters since boot
+#[derive(Serialize, Deserialize, Clone)]
+pub struct VmCounters {
+    pub page_faults: u64,
+    pub major_page_faults: u64,
+    /// Pages swapped in
+    pub swap_in: u64,
+    /// Pages swapped out
+    pub swap_out: u64,
+    /// `None` on kernels older than 4.13
+    pub oom_kills: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DiskInfo {
+    /// Mounted device, e.g. `/dev/nvme0n1p2`
+    pub name: String,
+    pub mount_point: String,
+    pub fs_type: String,
+    pub mount_options: Vec<String>,
+    pub read_only: bool,
+    pub total_space: u64,
+    pub available_space: u64,
+    /// Zero on filesystems without a fixed inode table, such as btrfs
+    pub total_inodes: u64,
+    pub used_inodes: u64,
+    pub free_inodes: u64,
+    pub is_removable: bool,
+}
+
+/// Per-device I/O rates averaged over the last sample interval
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DiskIoInfo {
+    pub name: String,
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+    pub reads_per_sec: f64,
+    pub writes_per_sec: f64,
+    /// Requests currently queued or being serviced
+    pub in_flight: u64,
+    /// Average time per completed request in milliseconds, including queueing
+    pub await_ms: f64,
+    /// Percentage of time the device was busy
+    pub utilization: f64,
+}
+
+/// Interface counters since boot plus rates over the last sample interval
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NetworkInfo {
+    pub received: u64,
+    pub transmitted: u64,
+    pub packets_received: u64,
+    pub packets_transmitted: u64,
+    pub errors_on_received: u64,
+    pub errors_on_transmitted: u64,
+    pub received_bytes_per_sec: f64,
+    pub transmitted_bytes_per_sec: f64,
+    pub packets_received_per_sec: f64,
+    pub packets_transmitted_per_sec: f64,
+}
+
+/// TCP counters since boot from `/proc/net/snmp` and `/proc/net/netstat`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TcpStats {
+    pub active_opens: u64,
+    pub passive_opens: u64,
+    pub attempt_fails: u64,
+    /// Established connections reset by either side
+    pub established_resets: u64,
+    pub current_established: u64,
+    pub in_segments: u64,
+    pub out_segments: u64,
+    pub retransmitted_segments: u64,
+    pub in_errors: u64,
+    /// Segments sent with the RST flag
+    pub out_resets: u64,
+    /// Times the accept queue of a listening socket overflowed
+    pub listen_overflows: u64,
+    /// SYNs dropped by listening sockets for any reason
+    pub listen_drops: u64,
+}
+
+/// A TCP or UDP socket and the process holding it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SocketInfo {
+    /// `tcp`, `tcp6`, `udp` or `udp6`
+    pub protocol: String,
+    pub local_address: String,
+    pub local_port: u16,
+    pub remote_address: String,
+    pub remote_port: u16,
+    /// TCP state such as `LISTEN` or `ESTABLISHED`; unconnected UDP sockets are `UNCONN`
+    pub state: String,
+    pub uid: u32,
+    /// `None` when the owner couldn't be found, e.g. without permission to read its fds
+    pub pid: Option<u32>,
+    pub process_name: Option<String>,
+}
+
+/// A temperature sensor; all values in degrees Celsius
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ComponentInfo {
+    pub label: String,
+    pub temperature: f32,
+    pub max: Option<f32>,
+    pub critical: Option<f32>,
+}
+
+/// Pressure Stall Information per resource; a resource is `None` when the kernel doesn't report it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PressureInfo {
+    pub cpu: Option<ResourcePressure>,
+    pub memory: Option<ResourcePressure>,
+    pub io: Option<ResourcePressure>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ResourcePressure {
+    /// Time at least one task was stalled on the resource
+    pub some: Option<PressureStall>,
+    /// Time all non-idle tasks were stalled at once
+    pub full: Option<PressureStall>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PressureStall {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    /// Cumulative stall time in microseconds
+    pub total: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PressureReport {
+    /// Host-wide pressure, `None` on kernels without PSI
+    pub host: Option<PressureInfo>,
+    pub cgroup: Option<CgroupPressure>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CgroupPressure {
+    pub path: String,
+    pub pressure: PressureInfo,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -50,6 +271,51 @@ pub struct ProcessSummary {
     pub total_count: usize,
     pub top_cpu_processes: Vec<ProcessInfo>,
     pub top_memory_processes: Vec<ProcessInfo>,
+    /// Only present when grouping was requested with `group_by`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_cpu_groups: Option<Vec<ProcessGroup>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_memory_groups: Option<Vec<ProcessGroup>>,
+    pub health: ProcessHealth,
+}
+
+/// Processes in states that top-N rankings don't reveal
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ProcessHealth {
+    /// Process count per state: `running`, `sleeping`, `disk_sleep`, `zombie`, `stopped`, `idle`, ...
+    pub states: HashMap<String, usize>,
+    /// Zombies waiting to be reaped, lowest PID first, at most 100
+    pub zombies: Vec<ZombieProcess>,
+    /// Processes in uninterruptible sleep (`D`), longest blocked first, at most 100
+    pub blocked: Vec<BlockedProcess>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ZombieProcess {
+    pub pid: u32,
+    pub name: String,
+    /// The parent that hasn't reaped it
+    pub ppid: u32,
+    pub parent_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BlockedProcess {
+    pub pid: u32,
+    pub name: String,
+    /// Kernel function it is waiting in, when the kernel exposes it
+    pub wchan: Option<String>,
+    /// Seconds it has been seen in `D` state across consecutive samples; a lower bound
+    pub blocked_secs: u64,
+}
+
+/// Processes sharing a name, executable, user or cgroup, with their usage summed
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProcessGroup {
+    pub key: String,
+    pub process_count: usize,
+    pub cpu_usage: f32,
+    pub memory: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -58,4 +324,369 @@ pub struct ProcessInfo {
     pub name: String,
     pub cpu_usage: f32,
     pub memory: u64,
-}
\ No newline at end of file
+    /// Effective UID, `None` if the process exited before it could be read
+    pub uid: Option<u32>,
+    pub user: Option<String>,
+    /// Set when the process runs inside a container
+    pub container: Option<ContainerRef>,
+    /// Unix time the process started, telling a reused PID apart
+    #[serde(skip)]
+    pub start_time: Option<u64>,
+    /// One-letter state as in `/proc/<pid>/stat`, for the health summary
+    #[serde(skip)]
+    pub state: Option<char>,
+    #[serde(skip)]
+    pub ppid: Option<u32>,
+}
+
+/// The container a process belongs to; `name` and `image` need a reachable runtime socket
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ContainerRef {
+    pub id: String,
+    /// `docker`, `containerd`, `cri-o`, `podman` or `kubernetes`
+    pub runtime: String,
+    pub name: Option<String>,
+    pub image: Option<String>,
+}
+
+/// Resource usage summed over a user's processes
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UserInfo {
+    pub uid: u32,
+    /// `None` for UIDs missing from `/etc/passwd`
+    pub name: Option<String>,
+    pub process_count: usize,
+    pub cpu_usage: f32,
+    pub memory: u64,
+}
+
+/// A login session from utmp
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SessionInfo {
+    pub user: String,
+    /// Terminal, e.g. `pts/0`
+    pub tty: String,
+    /// Remote host for SSH logins
+    pub host: Option<String>,
+    pub pid: i32,
+    /// Unix timestamp in seconds
+    pub login_time: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UsersReport {
+    pub users: Vec<UserInfo>,
+    pub sessions: Vec<SessionInfo>,
+}
+
+/// Resource usage summed over a container's processes
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ContainerInfo {
+    pub id: String,
+    pub runtime: String,
+    pub name: Option<String>,
+    pub image: Option<String>,
+    pub cpu_usage: f32,
+    pub memory: u64,
+    pub process_count: usize,
+}
+
+/// Everything we know about one process, read on request
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProcessDetail {
+    pub pid: u32,
+    pub ppid: u32,
+    pub name: String,
+    pub cmdline: Vec<String>,
+    /// One-letter state such as `R`, `S`, `D` or `Z`
+    pub state: String,
+    pub threads: u64,
+    /// Resident set size in bytes
+    pub memory: u64,
+    /// Seconds of CPU time used since the process started
+    pub cpu_time: f64,
+    /// Unix timestamp in seconds
+    pub start_time: u64,
+    /// cgroup v2 path, `None` on hosts without the unified hierarchy
+    pub cgroup: Option<String>,
+}
+
+/// Open file descriptors of one process. Parts that couldn't be read,
+/// usually for lack of permission, are `None` with the reason in `errors`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProcessFiles {
+    pub pid: u32,
+    /// Number of open descriptors
+    pub count: Option<usize>,
+    /// `Max open files` limits; `None` when unlimited or unreadable
+    pub soft_limit: Option<u64>,
+    pub hard_limit: Option<u64>,
+    /// Descriptor count per type (`file`, `socket`, `pipe`, `anon_inode`, `device`, `other`)
+    pub by_type: Option<HashMap<String, usize>>,
+    /// The lowest-numbered descriptors, up to the requested limit
+    pub files: Option<Vec<OpenFile>>,
+    /// Whether `files` was cut short by the limit
+    pub truncated: bool,
+    /// Field name to error message
+    pub errors: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OpenFile {
+    pub fd: u32,
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Link target, e.g. `/var/log/app.log` or `socket:[12345]`
+    pub target: String,
+}
+
+/// Memory mappings of one process, with per-field errors as in [`ProcessFiles`]
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProcessMaps {
+    pub pid: u32,
+    /// Totals from `smaps_rollup`
+    pub rollup: Option<MemoryRollup>,
+    /// Mappings summed per path, largest RSS first, up to the requested limit
+    pub mappings: Option<Vec<MemoryMapping>>,
+    /// Number of distinct paths before the limit was applied
+    pub total_mappings: Option<usize>,
+    pub truncated: bool,
+    pub errors: HashMap<String, String>,
+}
+
+/// Bytes; the `pss_*` breakdown is `None` on kernels older than 5.13
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MemoryRollup {
+    pub rss: u64,
+    pub pss: u64,
+    pub pss_anon: Option<u64>,
+    pub pss_file: Option<u64>,
+    pub pss_shmem: Option<u64>,
+    pub shared_clean: u64,
+    pub shared_dirty: u64,
+    pub private_clean: u64,
+    pub private_dirty: u64,
+    pub swap: u64,
+    pub swap_pss: u64,
+}
+
+/// All mappings of one file, or of anonymous memory, in bytes
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MemoryMapping {
+    /// File path, a pseudo path such as `[heap]` or `[stack]`, or `[anon]`
+    pub path: String,
+    /// How many separate mappings were summed
+    pub count: usize,
+    pub size: u64,
+    pub rss: u64,
+    pub pss: u64,
+    pub swap: u64,
+}
+
+/// Threads of one process, with per-field errors as in [`ProcessFiles`]
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProcessThreads {
+    pub pid: u32,
+    pub count: Option<usize>,
+    /// Busiest threads first, up to the requested limit
+    pub threads: Option<Vec<ThreadInfo>>,
+    pub truncated: bool,
+    pub errors: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ThreadInfo {
+    pub tid: u32,
+    pub name: String,
+    /// One-letter state such as `R`, `S`, `D` or `Z`
+    pub state: String,
+    /// Seconds of CPU time used since the thread started
+    pub cpu_time: f64,
+    /// Percent of one core, averaged over the thread's lifetime rather than measured now
+    pub avg_cpu_usage: f64,
+}
+
+/// A signal or priority change that was applied to a process
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProcessAction {
+    pub pid: u32,
+    pub name: String,
+    pub user: Option<String>,
+    pub exe: Option<String>,
+    /// `SIGTERM`, `SIGKILL`, ... or `renice <n>`
+    pub action: String,
+}
+
+/// One cgroup v2 node with its resource usage; controllers that aren't enabled are `None`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CgroupInfo {
+    /// Path relative to the cgroup root, `/` for the root itself
+    pub path: String,
+    pub cpu: Option<CgroupCpu>,
+    pub memory: Option<CgroupMemory>,
+    pub io: Vec<CgroupIo>,
+    pub pids: Option<u64>,
+    pub children: Vec<CgroupInfo>,
+}
+
+/// From `cpu.stat`, in microseconds
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CgroupCpu {
+    pub usage_usec: u64,
+    pub user_usec: u64,
+    pub system_usec: u64,
+    /// Only present when the cpu controller is enabled
+    pub nr_periods: Option<u64>,
+    pub nr_throttled: Option<u64>,
+    pub throttled_usec: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CgroupMemory {
+    pub current: u64,
+    /// `None` when unlimited
+    pub max: Option<u64>,
+}
+
+/// From `io.stat`, one entry per device
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CgroupIo {
+    /// `major:minor` device number
+    pub device: String,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub read_ios: u64,
+    pub write_ios: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessEventKind {
+    Started,
+    Exited,
+}
+
+impl ProcessEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Started => "started",
+            Self::Exited => "exited",
+        }
+    }
+}
+
+/// A process that appeared or disappeared between two samples
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProcessEvent {
+    /// Increases by one per event; pass it back as `since` to resume
+    pub id: u64,
+    pub kind: ProcessEventKind,
+    /// When the sampler noticed the change, not the exact start or exit time
+    pub timestamp: DateTime<Utc>,
+    pub pid: u32,
+    pub name: String,
+    pub cmdline: Vec<String>,
+    /// Unix timestamp in seconds, `None` if the process was gone before it could be read
+    pub start_time: Option<u64>,
+    /// Seconds the process ran for, only on `exited`
+    pub runtime_secs: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    /// The condition holds but not yet for the rule's `for` duration
+    Pending,
+    Firing,
+    Resolved,
+}
+
+impl AlertState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Firing => "firing",
+            Self::Resolved => "resolved",
+        }
+    }
+}
+
+/// One alert rule applied to one instance (a disk, an interface, ...) or to a plain metric
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Alert {
+    pub rule: String,
+    pub expr: String,
+    /// Mount point, device, interface, sensor, container or user; `None` for plain metrics
+    pub instance: Option<String>,
+    pub state: AlertState,
+    /// Value at the last sample where the condition held
+    pub value: f64,
+    pub threshold: f64,
+    /// When the condition started holding
+    pub since: DateTime<Utc>,
+    pub fired_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AlertRuleInfo {
+    pub name: String,
+    pub expr: String,
+}
+
+/// A stored metric series, as listed by `/api/v1/history/series`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistorySeriesInfo {
+    /// Metric with the instance as `*`, e.g. `disk.*.used_pct`
+    pub metric: String,
+    pub instance: Option<String>,
+}
+
+/// Stored values of one metric for one instance
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistorySeries {
+    pub metric: String,
+    pub instance: Option<String>,
+    /// `raw`, `1m` or `10m`
+    pub resolution: String,
+    pub points: Vec<HistoryPoint>,
+}
+
+/// Average, minimum and maximum over the bucket starting at `timestamp`; all equal for raw samples
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryPoint {
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// When a disk, memory or swap will reach a usage threshold, from a line fitted to its history
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Forecast {
+    /// `disk`, `memory` or `swap`
+    pub resource: String,
+    /// Mount point for disks
+    pub instance: Option<String>,
+    pub metric: String,
+    /// Latest stored usage in percent
+    pub current: f64,
+    pub threshold: f64,
+    /// Slope of the fitted line in percentage points per hour
+    pub rate_per_hour: f64,
+    /// Seconds until the threshold is reached at that rate; `None` if usage isn't rising
+    pub eta_secs: Option<u64>,
+    pub eta: Option<DateTime<Utc>>,
+    /// ETA at the slope plus two standard errors
+    pub eta_earliest_secs: Option<u64>,
+    /// ETA at the slope minus two standard errors; `None` if a flat or falling trend is plausible
+    pub eta_latest_secs: Option<u64>,
+    /// How well a straight line fits the window, 0 to 1
+    pub r_squared: f64,
+    /// `high`, `medium` or `low`, from the fit and the number of points
+    pub confidence: String,
+    /// Stored points the line was fitted to
+    pub points: usize,
+    /// E.g. `/var will be full in ~3h at the current rate (+1.20%/h)`
+    pub summary: String,
+}
@@ -0,0 +1,460 @@
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use crate::models::{Alert, AlertState};
+
+/// Deliveries waiting per webhook before new ones are dropped
+const QUEUE_SIZE: usize = 100;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// First retry delay, doubled after every failed attempt up to `MAX_BACKOFF`
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Placeholders a body template may use: the alert's fields plus the host name
+const FIELDS: &[&str] = &[
+    "rule",
+    "expr",
+    "instance",
+    "state",
+    "value",
+    "threshold",
+    "since",
+    "fired_at",
+    "resolved_at",
+    "hostname",
+];
+
+/// Where alert transitions are POSTed and how
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WebhookConfig {
+    pub urls: Vec<String>,
+    /// Key for the `X-Signature-256` HMAC-SHA256 header; unsigned without one
+    pub secret: Option<String>,
+    /// JSON body with `{{field}}` placeholders; the alert itself when unset
+    pub template: Option<Value>,
+    /// Attempts after the first one for connection errors, 429 and 5xx responses
+    pub retries: u32,
+    /// A transition to the state last sent for the same alert within this window is not sent again.
+    /// Only repeats are dropped: an alert flapping between firing and resolved sends every change,
+    /// which a rule's `for` duration damps.
+    pub dedup_window: Duration,
+}
+
+/// Parse a body template and check that it only uses known placeholders
+pub fn parse_template(s: &str) -> anyhow::Result<Value> {
+    let template: Value = serde_json::from_str(s).map_err(|e| anyhow::anyhow!("template is not valid JSON: {}", e))?;
+    let fields = FIELDS.iter().map(|field| (field.to_string(), Value::Null)).collect();
+    render(&template, &fields).map_err(|field| {
+        anyhow::anyhow!("unknown placeholder {{{{{}}}}} in template, expected one of {}", field, FIELDS.join(", "))
+    })?;
+    Ok(template)
+}
+
+/// Fill in the placeholders of every string in `template`. A string that is a single
+/// placeholder takes the field's JSON value, so `"{{value}}"` stays a number; elsewhere
+/// fields are inserted as text. Returns the unknown field name on failure.
+fn render(template: &Value, fields: &serde_json::Map<String, Value>) -> Result<Value, String> {
+    Ok(match template {
+        Value::String(s) => {
+            let whole = s
+                .trim()
+                .strip_prefix("{{")
+                .and_then(|rest| rest.strip_suffix("}}"))
+                .filter(|name| !name.contains("{{") && !name.contains("}}"));
+            if let Some(name) = whole {
+                let name = name.trim();
+                return fields.get(name).cloned().ok_or_else(|| name.to_string());
+            }
+            let mut out = String::with_capacity(s.len());
+            let mut rest = s.as_str();
+            while let Some(start) = rest.find("{{") {
+                let Some(end) = rest[start..].find("}}") else { break };
+                let name = rest[start + 2..start + end].trim();
+                let value = fields.get(name).ok_or_else(|| name.to_string())?;
+                out.push_str(&rest[..start]);
+                match value {
+                    Value::Null => {}
+                    Value::String(text) => out.push_str(text),
+                    other => out.push_str(&other.to_string()),
+                }
+                rest = &rest[start + end + 2..];
+            }
+            out.push_str(rest);
+            Value::String(out)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| render(item, fields)).collect::<Result<_, _>>()?),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| Ok((key.clone(), render(value, fields)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// One rendered notification, sent as-is on every attempt
+struct Delivery {
+    /// Stays the same across retries so receivers can drop duplicates
+    id: String,
+    event: &'static str,
+    body: Vec<u8>,
+    signature: Option<String>,
+    retries: u32,
+    /// `rule firing for instance`, for the logs
+    summary: String,
+}
+
+/// POSTs alert transitions to the configured webhooks. Each URL has its own queue and
+/// worker, so a slow or failing receiver delays only its own notifications, which are
+/// delivered in order.
+pub struct Notifier {
+    client: reqwest::Client,
+    /// First retry delay, `INITIAL_BACKOFF` outside tests
+    backoff: Duration,
+    state: Mutex<NotifierState>,
+}
+
+struct NotifierState {
+    config: WebhookConfig,
+    workers: HashMap<String, mpsc::Sender<Delivery>>,
+    /// Last state sent per rule and instance, for deduplication
+    sent: HashMap<(String, Option<String>), (AlertState, Instant)>,
+    next_id: u64,
+}
+
+impl Notifier {
+    pub fn new(config: WebhookConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("sysinfo-api/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("failed to build the webhook HTTP client");
+        Self {
+            client,
+            backoff: INITIAL_BACKOFF,
+            state: Mutex::new(NotifierState {
+                config,
+                workers: HashMap::new(),
+                sent: HashMap::new(),
+                next_id: 1,
+            }),
+        }
+    }
+
+    /// Replace the webhook settings after a config reload. Workers of removed URLs
+    /// finish what is already queued and stop.
+    pub fn set_config(&self, config: WebhookConfig) {
+        let mut state = self.state.lock().unwrap();
+        if state.config == config {
+            return;
+        }
+        state.workers.retain(|url, _| config.urls.contains(url));
+        tracing::info!("Webhooks reloaded ({} URL(s))", config.urls.len());
+        state.config = config;
+    }
+
+    /// Queue a notification per webhook for each firing or resolved transition
+    pub fn notify(&self, alerts: &[Alert], hostname: Option<&str>) {
+        let mut state = self.state.lock().unwrap();
+        if alerts.is_empty() || state.config.urls.is_empty() {
+            return;
+        }
+        let NotifierState { config, workers, sent, next_id } = &mut *state;
+        sent.retain(|_, (_, at)| at.elapsed() < config.dedup_window);
+
+        for alert in alerts {
+            let key = (alert.rule.clone(), alert.instance.clone());
+            let event = alert.state.as_str();
+            let summary = match &alert.instance {
+                Some(instance) => format!("{} {} for {}", alert.rule, event, instance),
+                None => format!("{} {}", alert.rule, event),
+            };
+            if sent.get(&key).is_some_and(|(state, _)| *state == alert.state) {
+                tracing::debug!("Not notifying {} again within the dedup window", summary);
+                continue;
+            }
+            sent.insert(key, (alert.state, Instant::now()));
+
+            let mut fields = match serde_json::to_value(alert) {
+                Ok(Value::Object(fields)) => fields,
+                _ => continue,
+            };
+            fields.insert("hostname".to_string(), hostname.into());
+            let body = match &config.template {
+                Some(template) => render(template, &fields).unwrap_or(Value::Object(fields)),
+                None => Value::Object(fields),
+            };
+            let body = body.to_string().into_bytes();
+            let signature = config.secret.as_deref().map(|secret| sign(secret, &body));
+
+            let id = format!("{}-{}", alert.since.timestamp_millis(), *next_id);
+            *next_id += 1;
+            for url in &config.urls {
+                let delivery = Delivery {
+                    id: id.clone(),
+                    event,
+                    body: body.clone(),
+                    signature: signature.clone(),
+                    retries: config.retries,
+                    summary: summary.clone(),
+                };
+                let worker = workers.entry(url.clone()).or_insert_with(|| {
+                    let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+                    tokio::spawn(deliver(self.client.clone(), url.clone(), self.backoff, rx));
+                    tx
+                });
+                if worker.try_send(delivery).is_err() {
+                    tracing::warn!("Webhook {} queue is full, dropping notification for {}", url, summary);
+                }
+            }
+        }
+    }
+}
+
+/// `sha256=` followed by the hex HMAC-SHA256 of `body`
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Send queued deliveries to `url` one at a time until the queue is dropped
+async fn deliver(client: reqwest::Client, url: String, initial_backoff: Duration, mut rx: mpsc::Receiver<Delivery>) {
+    while let Some(delivery) = rx.recv().await {
+        let mut backoff = initial_backoff;
+        for attempt in 0..=delivery.retries {
+            let result = post(&client, &url, &delivery).await;
+            match result {
+                Ok(()) => {
+                    tracing::info!("Webhook {} notified: {}", url, delivery.summary);
+                    break;
+                }
+                Err((e, true)) if attempt < delivery.retries => {
+                    tracing::warn!(
+                        "Webhook {} failed for {}: {}; retrying in {:?}",
+                        url,
+                        delivery.summary,
+                        e,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+                Err((e, _)) => {
+                    tracing::error!("Webhook {} failed for {}, giving up: {}", url, delivery.summary, e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// POST one attempt; errors say whether trying again could help
+async fn post(client: &reqwest::Client, url: &str, delivery: &Delivery) -> Result<(), (String, bool)> {
+    let mut request = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-Webhook-Id", &delivery.id)
+        .header("X-Webhook-Event", delivery.event)
+        .body(delivery.body.clone());
+    if let Some(signature) = &delivery.signature {
+        request = request.header("X-Signature-256", signature);
+    }
+    let response = request.send().await.map_err(|e| (e.to_string(), true))?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let retryable = status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+    Err((format!("HTTP {}", status), retryable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, StatusCode};
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+
+    /// Headers and body of a request the receiver got
+    type Received = (HeaderMap, Value);
+
+    /// A local webhook receiver answering with `statuses` in turn, then 200
+    async fn receiver(statuses: Vec<StatusCode>) -> (String, Arc<Mutex<Vec<Received>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let statuses = Arc::new(Mutex::new(VecDeque::from(statuses)));
+        let app = axum::Router::new().route(
+            "/hook",
+            axum::routing::post({
+                let received = received.clone();
+                move |headers: HeaderMap, body: axum::body::Bytes| async move {
+                    received
+                        .lock()
+                        .unwrap()
+                        .push((headers, serde_json::from_slice(&body).unwrap()));
+                    statuses.lock().unwrap().pop_front().unwrap_or(StatusCode::OK)
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, received)
+    }
+
+    fn notifier(config: WebhookConfig) -> Notifier {
+        Notifier {
+            backoff: Duration::from_millis(10),
+            ..Notifier::new(config)
+        }
+    }
+
+    fn alert(state: AlertState) -> Alert {
+        Alert {
+            rule: "root_full".to_string(),
+            expr: "disk./.used_pct > 85".to_string(),
+            instance: Some("/".to_string()),
+            state,
+            value: 91.5,
+            threshold: 85.0,
+            since: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            fired_at: None,
+            resolved_at: None,
+        }
+    }
+
+    /// Wait until the receiver has `count` requests, then a little longer to catch extra ones
+    async fn wait_for(received: &Mutex<Vec<Received>>, count: usize) -> Vec<Received> {
+        for _ in 0..200 {
+            if received.lock().unwrap().len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        received.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn renders_the_template_and_signs_the_body() {
+        let (url, received) = receiver(vec![]).await;
+        let template = parse_template(r#"{"text": "{{rule}} is {{state}} on {{hostname}}", "value": "{{value}}"}"#).unwrap();
+        let notifier = notifier(WebhookConfig {
+            urls: vec![url],
+            secret: Some("s3cret".to_string()),
+            template: Some(template),
+            ..Default::default()
+        });
+        notifier.notify(&[alert(AlertState::Firing)], Some("web1"));
+
+        let received = wait_for(&received, 1).await;
+        assert_eq!(received.len(), 1);
+        let (headers, body) = &received[0];
+        assert_eq!(body, &serde_json::json!({"text": "root_full is firing on web1", "value": 91.5}));
+        assert_eq!(headers["X-Webhook-Event"], "firing");
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+        mac.update(body.to_string().as_bytes());
+        let expected = hex::decode(headers["X-Signature-256"].to_str().unwrap().strip_prefix("sha256=").unwrap());
+        assert!(mac.verify_slice(&expected.unwrap()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn sends_the_alert_without_a_template() {
+        let (url, received) = receiver(vec![]).await;
+        let notifier = notifier(WebhookConfig {
+            urls: vec![url],
+            ..Default::default()
+        });
+        notifier.notify(&[alert(AlertState::Firing)], None);
+
+        let received = wait_for(&received, 1).await;
+        let (headers, body) = &received[0];
+        assert!(headers.get("X-Signature-256").is_none());
+        assert_eq!(body["rule"], "root_full");
+        assert_eq!(body["instance"], "/");
+        assert_eq!(body["hostname"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors_and_rate_limits() {
+        let (url, received) =
+            receiver(vec![StatusCode::INTERNAL_SERVER_ERROR, StatusCode::TOO_MANY_REQUESTS]).await;
+        let notifier = notifier(WebhookConfig {
+            urls: vec![url],
+            retries: 5,
+            ..Default::default()
+        });
+        notifier.notify(&[alert(AlertState::Firing)], None);
+
+        let received = wait_for(&received, 3).await;
+        assert_eq!(received.len(), 3);
+        // Every attempt carries the same delivery ID
+        let id = received[0].0["X-Webhook-Id"].clone();
+        assert!(received.iter().all(|(headers, _)| headers["X-Webhook-Id"] == id));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_last_retry() {
+        let (url, received) = receiver(vec![StatusCode::BAD_GATEWAY; 5]).await;
+        let notifier = notifier(WebhookConfig {
+            urls: vec![url],
+            retries: 2,
+            ..Default::default()
+        });
+        notifier.notify(&[alert(AlertState::Firing)], None);
+
+        assert_eq!(wait_for(&received, 3).await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (url, received) = receiver(vec![StatusCode::BAD_REQUEST]).await;
+        let notifier = notifier(WebhookConfig {
+            urls: vec![url],
+            retries: 5,
+            ..Default::default()
+        });
+        notifier.notify(&[alert(AlertState::Firing)], None);
+
+        assert_eq!(wait_for(&received, 1).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn drops_repeats_within_the_dedup_window() {
+        let (url, received) = receiver(vec![]).await;
+        let notifier = notifier(WebhookConfig {
+            urls: vec![url],
+            dedup_window: Duration::from_secs(300),
+            ..Default::default()
+        });
+        notifier.notify(&[alert(AlertState::Firing)], None);
+        notifier.notify(&[alert(AlertState::Firing)], None);
+        // A change of state is always sent, flapping included
+        notifier.notify(&[alert(AlertState::Resolved)], None);
+        notifier.notify(&[alert(AlertState::Firing)], None);
+
+        let received = wait_for(&received, 3).await;
+        let events: Vec<_> = received.iter().map(|(headers, _)| headers["X-Webhook-Event"].clone()).collect();
+        assert_eq!(events, ["firing", "resolved", "firing"]);
+    }
+
+    #[tokio::test]
+    async fn sends_repeats_after_the_dedup_window() {
+        let (url, received) = receiver(vec![]).await;
+        let notifier = notifier(WebhookConfig {
+            urls: vec![url],
+            dedup_window: Duration::from_millis(50),
+            ..Default::default()
+        });
+        notifier.notify(&[alert(AlertState::Firing)], None);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        notifier.notify(&[alert(AlertState::Firing)], None);
+
+        assert_eq!(wait_for(&received, 2).await.len(), 2);
+    }
+}
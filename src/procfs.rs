@@ -0,0 +1,1147 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::CString;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// Where the host's procfs, sysfs and root filesystem are mounted.
+///
+/// Defaults to the local `/proc`, `/sys` and `/`; in a container they point at
+/// bind mounts of the host (e.g. `/host/proc`) so we report the host, not the container.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostPaths {
+    pub proc: PathBuf,
+    pub sys: PathBuf,
+    pub root: PathBuf,
+}
+
+impl Default for HostPaths {
+    fn default() -> Self {
+        Self {
+            proc: PathBuf::from("/proc"),
+            sys: PathBuf::from("/sys"),
+            root: PathBuf::from("/"),
+        }
+    }
+}
+
+impl HostPaths {
+    /// Build from `HOST_PROC`/`HOST_SYS`/`HOST_ROOT`; proc and sys default to
+    /// `<root>/proc` and `<root>/sys` when only the root is given
+    pub fn new(proc: Option<PathBuf>, sys: Option<PathBuf>, root: Option<PathBuf>) -> Self {
+        let root = root.unwrap_or_else(|| PathBuf::from("/"));
+        Self {
+            proc: proc.unwrap_or_else(|| root.join("proc")),
+            sys: sys.unwrap_or_else(|| root.join("sys")),
+            root,
+        }
+    }
+
+    /// Whether any path points somewhere other than the local filesystem
+    pub fn is_overridden(&self) -> bool {
+        *self != Self::default()
+    }
+
+    pub fn proc(&self, rel: impl AsRef<Path>) -> PathBuf {
+        self.proc.join(rel)
+    }
+
+    pub fn sys(&self, rel: impl AsRef<Path>) -> PathBuf {
+        self.sys.join(rel)
+    }
+
+    /// Resolve an absolute host path (e.g. a mount point) under the host root
+    pub fn root(&self, host_path: impl AsRef<Path>) -> PathBuf {
+        let host_path = host_path.as_ref();
+        self.root.join(host_path.strip_prefix("/").unwrap_or(host_path))
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// `/proc/meminfo` as bytes keyed by field name
+pub fn read_meminfo(paths: &HostPaths) -> io::Result<HashMap<String, u64>> {
+    let content = std::fs::read_to_string(paths.proc("meminfo"))?;
+    Ok(content.lines().filter_map(parse_size_line).collect())
+}
+
+/// A `Key:   123 kB` line as `(key, bytes)`; values without a unit are kept as they are
+fn parse_size_line(line: &str) -> Option<(String, u64)> {
+    let (key, rest) = line.split_once(':')?;
+    let mut parts = rest.split_whitespace();
+    let value: u64 = parts.next()?.parse().ok()?;
+    let value = match parts.next() {
+        Some("kB") => value * 1024,
+        _ => value,
+    };
+    Some((key.to_string(), value))
+}
+
+/// Cumulative VM event counters from `/proc/vmstat` keyed by name
+pub fn read_vmstat(paths: &HostPaths) -> io::Result<HashMap<String, u64>> {
+    read_key_values(&paths.proc("vmstat"))
+}
+
+/// Files of `key value` lines such as `/proc/vmstat` or a cgroup's `cpu.stat`
+pub fn read_key_values(path: &Path) -> io::Result<HashMap<String, u64>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            Some((key.to_string(), value.trim().parse().ok()?))
+        })
+        .collect())
+}
+
+/// `/etc/os-release` of the host, falling back to `/usr/lib/os-release`
+pub fn read_os_release(paths: &HostPaths) -> HashMap<String, String> {
+    let content = std::fs::read_to_string(paths.root("/etc/os-release"))
+        .or_else(|_| std::fs::read_to_string(paths.root("/usr/lib/os-release")))
+        .unwrap_or_default();
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            Some((key.trim().to_string(), value.trim().trim_matches('"').to_string()))
+        })
+        .collect()
+}
+
+/// The host's configured hostname. `/proc/sys/kernel/hostname` reflects the
+/// reader's UTS namespace, so `/etc/hostname` on the host root is tried first.
+pub fn read_hostname(paths: &HostPaths) -> Option<String> {
+    read_trimmed(&paths.root("/etc/hostname")).or_else(|| read_trimmed(&paths.proc("sys/kernel/hostname")))
+}
+
+pub fn read_kernel_release(paths: &HostPaths) -> Option<String> {
+    read_trimmed(&paths.proc("sys/kernel/osrelease"))
+}
+
+/// Seconds since boot from `/proc/uptime`
+pub fn read_uptime(paths: &HostPaths) -> Option<u64> {
+    let content = std::fs::read_to_string(paths.proc("uptime")).ok()?;
+    let secs: f64 = content.split_whitespace().next()?.parse().ok()?;
+    Some(secs as u64)
+}
+
+/// Boot time as a Unix timestamp from the `btime` line of `/proc/stat`
+pub fn read_boot_time(paths: &HostPaths) -> Option<u64> {
+    let content = std::fs::read_to_string(paths.proc("stat")).ok()?;
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Cumulative CPU time counters from one `cpu` line of `/proc/stat`, in clock ticks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuTicks {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+    pub guest: u64,
+    pub guest_nice: u64,
+}
+
+impl CpuTicks {
+    /// Total elapsed ticks; guest time is already included in user/nice
+    pub fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq + self.steal
+    }
+
+    pub fn saturating_sub(&self, prev: &CpuTicks) -> CpuTicks {
+        CpuTicks {
+            user: self.user.saturating_sub(prev.user),
+            nice: self.nice.saturating_sub(prev.nice),
+            system: self.system.saturating_sub(prev.system),
+            idle: self.idle.saturating_sub(prev.idle),
+            iowait: self.iowait.saturating_sub(prev.iowait),
+            irq: self.irq.saturating_sub(prev.irq),
+            softirq: self.softirq.saturating_sub(prev.softirq),
+            steal: self.steal.saturating_sub(prev.steal),
+            guest: self.guest.saturating_sub(prev.guest),
+            guest_nice: self.guest_nice.saturating_sub(prev.guest_nice),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CpuStat {
+    pub total: CpuTicks,
+    /// Per-core counters keyed by name (`cpu0`, `cpu1`, ...)
+    pub cores: HashMap<String, CpuTicks>,
+    pub procs_running: u64,
+    pub procs_blocked: u64,
+}
+
+pub fn read_cpu_stat(paths: &HostPaths) -> io::Result<CpuStat> {
+    let content = std::fs::read_to_string(paths.proc("stat"))?;
+    let mut stat = CpuStat::default();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let Some(key) = fields.next() else {
+            continue;
+        };
+        if key.starts_with("cpu") {
+            let values: Vec<u64> = fields.filter_map(|v| v.parse().ok()).collect();
+            // Older kernels report fewer columns; missing ones are zero
+            let value = |i: usize| values.get(i).copied().unwrap_or_default();
+            let ticks = CpuTicks {
+                user: value(0),
+                nice: value(1),
+                system: value(2),
+                idle: value(3),
+                iowait: value(4),
+                irq: value(5),
+                softirq: value(6),
+                steal: value(7),
+                guest: value(8),
+                guest_nice: value(9),
+            };
+            if key == "cpu" {
+                stat.total = ticks;
+            } else {
+                stat.cores.insert(key.to_string(), ticks);
+            }
+        } else if key == "procs_running" {
+            stat.procs_running = fields.next().and_then(|v| v.parse().ok()).unwrap_or_default();
+        } else if key == "procs_blocked" {
+            stat.procs_blocked = fields.next().and_then(|v| v.parse().ok()).unwrap_or_default();
+        }
+    }
+    Ok(stat)
+}
+
+/// 1, 5 and 15 minute load averages from `/proc/loadavg`
+pub fn read_loadavg(paths: &HostPaths) -> io::Result<(f64, f64, f64)> {
+    let content = std::fs::read_to_string(paths.proc("loadavg"))?;
+    let mut values = content.split_whitespace().map(|v| v.parse::<f64>());
+    let mut next = || -> io::Result<f64> {
+        values
+            .next()
+            .and_then(Result::ok)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed loadavg"))
+    };
+    Ok((next()?, next()?, next()?))
+}
+
+/// One line (`some` or `full`) of a PSI file
+#[derive(Debug, Clone, Copy)]
+pub struct PressureLine {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    /// Total stall time in microseconds
+    pub total: u64,
+}
+
+/// Parse a PSI file such as `/proc/pressure/io` or `<cgroup>/io.pressure`
+/// into its `some` and `full` lines
+pub fn read_pressure_file(path: &Path) -> io::Result<(Option<PressureLine>, Option<PressureLine>)> {
+    let content = std::fs::read_to_string(path)?;
+    let mut some = None;
+    let mut full = None;
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next();
+        let values: HashMap<&str, &str> = fields.filter_map(|field| field.split_once('=')).collect();
+        let value = |key: &str| values.get(key).and_then(|v| v.parse::<f64>().ok());
+        let parsed = (|| {
+            Some(PressureLine {
+                avg10: value("avg10")?,
+                avg60: value("avg60")?,
+                avg300: value("avg300")?,
+                total: values.get("total")?.parse().ok()?,
+            })
+        })();
+        match kind {
+            Some("some") => some = parsed,
+            Some("full") => full = parsed,
+            _ => {}
+        }
+    }
+    Ok((some, full))
+}
+
+/// Mount point of the cgroup v2 hierarchy, whether unified or hybrid
+pub fn cgroup2_root(paths: &HostPaths) -> Option<PathBuf> {
+    ["fs/cgroup", "fs/cgroup/unified"]
+        .into_iter()
+        .map(|rel| paths.sys(rel))
+        .find(|dir| dir.join("cgroup.controllers").exists() || dir.join("cgroup.procs").exists())
+}
+
+/// Directory of `cgroup` (e.g. `/system.slice/nginx.service`) in the v2 hierarchy,
+/// rejecting paths that would escape it
+pub fn cgroup_dir(paths: &HostPaths, cgroup: &str) -> io::Result<PathBuf> {
+    let root = cgroup2_root(paths)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cgroup v2 hierarchy not mounted"))?;
+    let rel = Path::new(cgroup.trim_start_matches('/'));
+    if rel.components().any(|c| !matches!(c, std::path::Component::Normal(_))) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid cgroup path"));
+    }
+    let dir = root.join(rel);
+    if !dir.is_dir() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("cgroup {} not found", cgroup)));
+    }
+    Ok(dir)
+}
+
+/// A single-value cgroup file such as `memory.current`; `None` for `max` (no limit)
+pub fn read_cgroup_value(path: &Path) -> io::Result<Option<u64>> {
+    let content = std::fs::read_to_string(path)?;
+    match content.trim() {
+        "max" => Ok(None),
+        value => value
+            .parse()
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+    }
+}
+
+/// Per-device counters from a cgroup's `io.stat`, keyed by `major:minor`
+pub fn read_cgroup_io_stat(path: &Path) -> io::Result<Vec<(String, HashMap<String, u64>)>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?.to_string();
+            let counters = fields
+                .filter_map(|field| {
+                    let (key, value) = field.split_once('=')?;
+                    Some((key.to_string(), value.parse().ok()?))
+                })
+                .collect();
+            Some((device, counters))
+        })
+        .collect())
+}
+
+/// Child cgroup directories of `dir`, sorted by name
+pub fn cgroup_children(dir: &Path) -> Vec<PathBuf> {
+    let mut children: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.path())
+        .collect();
+    children.sort();
+    children
+}
+
+/// The v2 cgroup of `pid` from `/proc/<pid>/cgroup`, or `None` on a v1-only host
+pub fn read_pid_cgroup(paths: &HostPaths, pid: u32) -> io::Result<Option<String>> {
+    let content = std::fs::read_to_string(paths.proc(pid.to_string()).join("cgroup"))?;
+    Ok(content
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(str::to_string))
+}
+
+/// Path of the executable from the `/proc/<pid>/exe` link
+pub fn read_exe(paths: &HostPaths, pid: u32) -> io::Result<String> {
+    let target = std::fs::read_link(paths.proc(pid.to_string()).join("exe"))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+/// Arguments from `/proc/<pid>/cmdline`; empty for kernel threads
+pub fn read_cmdline(paths: &HostPaths, pid: u32) -> io::Result<Vec<String>> {
+    let content = std::fs::read(paths.proc(pid.to_string()).join("cmdline"))?;
+    Ok(content
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect())
+}
+
+/// Effective UID of `pid`, the second field of the `Uid:` line in `/proc/<pid>/status`.
+/// The `/proc/<pid>` directory itself belongs to root for non-dumpable processes.
+pub fn read_pid_uid(paths: &HostPaths, pid: u32) -> io::Result<u32> {
+    let content = std::fs::read_to_string(paths.proc(pid.to_string()).join("status"))?;
+    parse_status_uid(&content).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed status"))
+}
+
+fn parse_status_uid(content: &str) -> Option<u32> {
+    let line = content.lines().find_map(|line| line.strip_prefix("Uid:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// User names by UID from the host's `/etc/passwd`
+pub fn read_passwd(paths: &HostPaths) -> HashMap<u32, String> {
+    std::fs::read_to_string(paths.root("/etc/passwd"))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect()
+}
+
+/// A login session from utmp
+#[derive(Debug, Clone)]
+pub struct UtmpSession {
+    pub user: String,
+    pub line: String,
+    pub host: String,
+    pub pid: i32,
+    /// Unix timestamp in seconds
+    pub login_time: i64,
+}
+
+/// Logged-in users from the host's `/run/utmp` (glibc layout, 384-byte records).
+/// Systems without utmp simply have no sessions.
+pub fn read_utmp(paths: &HostPaths) -> Vec<UtmpSession> {
+    const RECORD_SIZE: usize = 384;
+    const USER_PROCESS: i16 = 7;
+
+    let Ok(content) = std::fs::read(paths.root("/run/utmp")).or_else(|_| std::fs::read(paths.root("/var/run/utmp")))
+    else {
+        return Vec::new();
+    };
+    let text = |bytes: &[u8]| {
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).into_owned()
+    };
+    content
+        .chunks_exact(RECORD_SIZE)
+        .filter(|record| i16::from_ne_bytes([record[0], record[1]]) == USER_PROCESS)
+        .map(|record| UtmpSession {
+            pid: i32::from_ne_bytes(record[4..8].try_into().unwrap()),
+            line: text(&record[8..40]),
+            user: text(&record[44..76]),
+            host: text(&record[76..332]),
+            login_time: i32::from_ne_bytes(record[340..344].try_into().unwrap()) as i64,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct MountEntry {
+    pub device: String,
+    pub mount_point: String,
+    pub fs_type: String,
+    pub options: Vec<String>,
+}
+
+/// Mount table of the host. With overridden paths this is PID 1's view, since
+/// our own mount namespace only shows the container's filesystems.
+pub fn read_mounts(paths: &HostPaths) -> io::Result<Vec<MountEntry>> {
+    let table = if paths.is_overridden() { "1/mounts" } else { "self/mounts" };
+    let content = std::fs::read_to_string(paths.proc(table))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(MountEntry {
+                device: unescape_mount_field(fields.next()?),
+                mount_point: unescape_mount_field(fields.next()?),
+                fs_type: fields.next()?.to_string(),
+                options: fields.next()?.split(',').map(str::to_string).collect(),
+            })
+        })
+        .collect())
+}
+
+/// Undo the octal escaping (`\040` for space, etc.) used in mount tables
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && i + 3 < bytes.len()
+            && let Some(value) = std::str::from_utf8(&bytes[i + 1..i + 4])
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 8).ok())
+        {
+            out.push(value);
+            i += 4;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Which mounts to leave out of the disk list
+#[derive(Debug, Clone, PartialEq)]
+pub struct MountFilter {
+    pub fs_types: Vec<String>,
+    /// Globs where `*` matches any run of characters, `/` included, and `?` any one
+    pub mount_points: Vec<String>,
+}
+
+impl Default for MountFilter {
+    /// Kernel-internal and virtual filesystems that don't represent storage
+    fn default() -> Self {
+        let fs_types = [
+            "rootfs", "sysfs", "proc", "devtmpfs", "devpts", "tmpfs", "cgroup", "cgroup2", "pstore",
+            "bpf", "tracefs", "debugfs", "securityfs", "configfs", "fusectl", "mqueue", "hugetlbfs",
+            "autofs", "binfmt_misc", "nsfs", "squashfs", "rpc_pipefs", "iso9660", "overlay",
+        ];
+        Self {
+            fs_types: fs_types.iter().map(|t| t.to_string()).collect(),
+            mount_points: ["/sys", "/sys/*", "/proc", "/proc/*", "/run/user/*", "/run/docker/*"]
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl MountFilter {
+    /// `/` is never left out for its type: in a container it is usually `overlay`,
+    /// and it's still the filesystem the service and its neighbours write to
+    pub fn excludes(&self, mount: &MountEntry) -> bool {
+        (mount.mount_point != "/" && self.fs_types.contains(&mount.fs_type))
+            || self.mount_points.iter().any(|p| glob_match(p, &mount.mount_point))
+    }
+}
+
+/// Minimal glob matching with `*` and `?`, backtracking to the last `*`
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FsStats {
+    pub total: u64,
+    pub available: u64,
+    pub inodes: u64,
+    pub inodes_free: u64,
+}
+
+pub fn statvfs(path: &Path) -> io::Result<FsStats> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `c_path` is NUL-terminated and `stat` is a valid out pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let block = stat.f_frsize as u64;
+    Ok(FsStats {
+        total: stat.f_blocks as u64 * block,
+        available: stat.f_bavail as u64 * block,
+        inodes: stat.f_files as u64,
+        inodes_free: stat.f_ffree as u64,
+    })
+}
+
+/// Whether the block device behind `device` (e.g. `/dev/sdb1`) is removable
+pub fn is_removable(paths: &HostPaths, device: &str) -> bool {
+    let Some(name) = device.strip_prefix("/dev/") else {
+        return false;
+    };
+    let Ok(dev_dir) = std::fs::canonicalize(paths.sys("class/block").join(name)) else {
+        return false;
+    };
+    // Partitions live inside their parent disk's directory, which holds the flag
+    [dev_dir.join("removable"), dev_dir.join("../removable")]
+        .iter()
+        .find_map(|path| read_trimmed(path))
+        .is_some_and(|flag| flag == "1")
+}
+
+/// Cumulative counters for one block device from `/proc/diskstats`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskStat {
+    pub reads: u64,
+    pub sectors_read: u64,
+    /// Milliseconds spent on reads
+    pub read_ticks: u64,
+    pub writes: u64,
+    pub sectors_written: u64,
+    pub write_ticks: u64,
+    pub in_flight: u64,
+    /// Milliseconds the device had I/O in progress
+    pub io_ticks: u64,
+}
+
+impl DiskStat {
+    /// Counter deltas since `prev`; `in_flight` is a gauge and is kept as-is
+    pub fn saturating_sub(&self, prev: &DiskStat) -> DiskStat {
+        DiskStat {
+            reads: self.reads.saturating_sub(prev.reads),
+            sectors_read: self.sectors_read.saturating_sub(prev.sectors_read),
+            read_ticks: self.read_ticks.saturating_sub(prev.read_ticks),
+            writes: self.writes.saturating_sub(prev.writes),
+            sectors_written: self.sectors_written.saturating_sub(prev.sectors_written),
+            write_ticks: self.write_ticks.saturating_sub(prev.write_ticks),
+            in_flight: self.in_flight,
+            io_ticks: self.io_ticks.saturating_sub(prev.io_ticks),
+        }
+    }
+}
+
+/// `/proc/diskstats` keyed by device name, skipping devices that never did any I/O
+pub fn read_diskstats(paths: &HostPaths) -> io::Result<HashMap<String, DiskStat>> {
+    let content = std::fs::read_to_string(paths.proc("diskstats"))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let name = fields.get(2)?;
+            let field = |n: usize| fields.get(n).and_then(|v| v.parse::<u64>().ok());
+            let stat = DiskStat {
+                reads: field(3)?,
+                sectors_read: field(5)?,
+                read_ticks: field(6)?,
+                writes: field(7)?,
+                sectors_written: field(9)?,
+                write_ticks: field(10)?,
+                in_flight: field(11)?,
+                io_ticks: field(12)?,
+            };
+            (stat.reads + stat.writes > 0).then(|| (name.to_string(), stat))
+        })
+        .collect())
+}
+
+/// Path of a per-network-namespace file under `net/`. `self/net` would show our own
+/// namespace, so a mounted host `/proc` is read through init's view instead.
+fn net_file(paths: &HostPaths, name: &str) -> PathBuf {
+    let dir = if paths.is_overridden() { "1/net" } else { "self/net" };
+    paths.proc(dir).join(name)
+}
+
+/// Cumulative counters for one interface from `/proc/net/dev`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NetDevStat {
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errors: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+}
+
+impl NetDevStat {
+    pub fn saturating_sub(&self, prev: &NetDevStat) -> NetDevStat {
+        NetDevStat {
+            rx_bytes: self.rx_bytes.saturating_sub(prev.rx_bytes),
+            rx_packets: self.rx_packets.saturating_sub(prev.rx_packets),
+            rx_errors: self.rx_errors.saturating_sub(prev.rx_errors),
+            tx_bytes: self.tx_bytes.saturating_sub(prev.tx_bytes),
+            tx_packets: self.tx_packets.saturating_sub(prev.tx_packets),
+            tx_errors: self.tx_errors.saturating_sub(prev.tx_errors),
+        }
+    }
+}
+
+/// `/proc/net/dev` keyed by interface name
+pub fn read_net_dev(paths: &HostPaths) -> io::Result<HashMap<String, NetDevStat>> {
+    let content = std::fs::read_to_string(net_file(paths, "dev"))?;
+    Ok(content
+        .lines()
+        // Two header lines, then `iface: rx... tx...`
+        .skip(2)
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let fields: Vec<u64> = counters.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+            let field = |n: usize| fields.get(n).copied();
+            let stat = NetDevStat {
+                rx_bytes: field(0)?,
+                rx_packets: field(1)?,
+                rx_errors: field(2)?,
+                tx_bytes: field(8)?,
+                tx_packets: field(9)?,
+                tx_errors: field(10)?,
+            };
+            Some((name.trim().to_string(), stat))
+        })
+        .collect())
+}
+
+/// Protocol counters from `/proc/net/snmp` or `/proc/net/netstat`, keyed `Proto:Name`
+/// (e.g. `Tcp:RetransSegs`). Each protocol is a header line followed by a value line.
+pub fn read_net_counters(paths: &HostPaths, file: &str) -> io::Result<HashMap<String, u64>> {
+    let content = std::fs::read_to_string(net_file(paths, file))?;
+    let mut counters = HashMap::new();
+    let mut lines = content.lines();
+    while let (Some(header), Some(values)) = (lines.next(), lines.next()) {
+        let (Some((proto, names)), Some((_, values))) = (header.split_once(':'), values.split_once(':')) else {
+            continue;
+        };
+        for (name, value) in names.split_whitespace().zip(values.split_whitespace()) {
+            // A few fields such as Tcp:MaxConn are signed; they are not counters
+            if let Ok(value) = value.parse() {
+                counters.insert(format!("{}:{}", proto, name), value);
+            }
+        }
+    }
+    Ok(counters)
+}
+
+/// One row of `/proc/net/{tcp,tcp6,udp,udp6}`
+#[derive(Debug, Clone)]
+pub struct SocketEntry {
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    /// Kernel `TCP_*` state number; UDP reuses the same numbering
+    pub state: u8,
+    pub uid: u32,
+    pub inode: u64,
+}
+
+/// Socket table for `protocol`, one of `tcp`, `tcp6`, `udp` or `udp6`
+pub fn read_sockets(paths: &HostPaths, protocol: &str) -> io::Result<Vec<SocketEntry>> {
+    let content = std::fs::read_to_string(net_file(paths, protocol))?;
+    Ok(content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            Some(SocketEntry {
+                local: parse_socket_addr(fields.get(1)?)?,
+                remote: parse_socket_addr(fields.get(2)?)?,
+                state: u8::from_str_radix(fields.get(3)?, 16).ok()?,
+                uid: fields.get(7)?.parse().ok()?,
+                inode: fields.get(9)?.parse().ok()?,
+            })
+        })
+        .collect())
+}
+
+/// Parse `0100007F:1F90` style addresses. The kernel prints the raw address as 32-bit
+/// words in host byte order, so each word's native bytes are the address bytes.
+fn parse_socket_addr(field: &str) -> Option<SocketAddr> {
+    let (addr, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let words: Vec<u32> = (0..addr.len() / 8)
+        .map(|i| u32::from_str_radix(&addr[i * 8..i * 8 + 8], 16))
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let ip = match words.as_slice() {
+        [a] => IpAddr::V4(Ipv4Addr::from(a.to_ne_bytes())),
+        [a, b, c, d] => {
+            let mut octets = [0u8; 16];
+            for (chunk, word) in octets.chunks_mut(4).zip([a, b, c, d]) {
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Map socket inodes to the PID holding them by walking every `/proc/<pid>/fd`.
+/// Processes we aren't allowed to inspect are silently skipped.
+pub fn socket_owners(paths: &HostPaths) -> io::Result<HashMap<u64, u32>> {
+    let mut owners = HashMap::new();
+    for pid in list_pids(paths)? {
+        let Ok(fds) = std::fs::read_dir(paths.proc(pid.to_string()).join("fd")) else {
+            continue;
+        };
+        for fd in fds.filter_map(Result::ok) {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            if let Some(inode) = target
+                .to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse().ok())
+            {
+                owners.entry(inode).or_insert(pid);
+            }
+        }
+    }
+    Ok(owners)
+}
+
+/// Process name from `/proc/<pid>/comm`
+pub fn read_comm(paths: &HostPaths, pid: u32) -> Option<String> {
+    read_trimmed(&paths.proc(pid.to_string()).join("comm"))
+}
+
+/// One temperature sensor, in degrees Celsius
+#[derive(Debug, Clone)]
+pub struct TemperatureSensor {
+    pub label: String,
+    pub temperature: f32,
+    pub max: Option<f32>,
+    pub critical: Option<f32>,
+}
+
+/// Temperature sensors from `/sys/class/hwmon` and `/sys/class/thermal`.
+/// Missing directories (VMs, containers without `/sys`) yield no sensors rather than an error.
+pub fn read_temperatures(paths: &HostPaths) -> Vec<TemperatureSensor> {
+    let (mut sensors, chips) = read_hwmon_temperatures(&paths.sys("class/hwmon"));
+    sensors.extend(read_thermal_zones(&paths.sys("class/thermal"), &chips));
+    sensors
+}
+
+/// `tempN_*` files are in millidegrees Celsius
+fn read_millidegrees(path: &Path) -> Option<f32> {
+    read_trimmed(path)?.parse::<i64>().ok().map(|v| v as f32 / 1000.0)
+}
+
+fn sorted_entries(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    entries
+}
+
+/// Sensors of every hwmon chip, and the names of the chips
+fn read_hwmon_temperatures(class_dir: &Path) -> (Vec<TemperatureSensor>, HashSet<String>) {
+    let mut sensors = Vec::new();
+    let mut chips = HashSet::new();
+    for chip in sorted_entries(class_dir, "hwmon") {
+        let name = read_trimmed(&chip.join("name")).unwrap_or_else(|| "hwmon".to_string());
+        chips.insert(name.clone());
+        for input in sorted_entries(&chip, "temp") {
+            let file_name = input.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let Some(index) = file_name.strip_suffix("_input") else {
+                continue;
+            };
+            let Some(temperature) = read_millidegrees(&input) else {
+                continue;
+            };
+            let label = match read_trimmed(&chip.join(format!("{}_label", index))) {
+                Some(label) => format!("{} {}", name, label),
+                None => format!("{} {}", name, index),
+            };
+            sensors.push(TemperatureSensor {
+                label,
+                temperature,
+                max: read_millidegrees(&chip.join(format!("{}_max", index))),
+                critical: read_millidegrees(&chip.join(format!("{}_crit", index))),
+            });
+        }
+    }
+    (sensors, chips)
+}
+
+/// Thermal zones, except those the kernel also registers as an hwmon chip (e.g. `acpitz`).
+/// Such a chip is named after the zone type, with `-` replaced by `_` on newer kernels.
+fn read_thermal_zones(class_dir: &Path, hwmon_chips: &HashSet<String>) -> Vec<TemperatureSensor> {
+    sorted_entries(class_dir, "thermal_zone")
+        .into_iter()
+        .filter_map(|zone| {
+            let kind = read_trimmed(&zone.join("type"));
+            if kind
+                .as_ref()
+                .is_some_and(|kind| hwmon_chips.contains(kind) || hwmon_chips.contains(&kind.replace('-', "_")))
+            {
+                return None;
+            }
+            let temperature = read_millidegrees(&zone.join("temp"))?;
+            let label = kind.unwrap_or_else(|| zone.file_name().unwrap_or_default().to_string_lossy().into_owned());
+            // The critical trip point is the temperature at which the kernel shuts down
+            let critical = (0..)
+                .map_while(|i| {
+                    let kind = read_trimmed(&zone.join(format!("trip_point_{}_type", i)))?;
+                    Some((kind, zone.join(format!("trip_point_{}_temp", i))))
+                })
+                .find(|(kind, _)| kind == "critical")
+                .and_then(|(_, path)| read_millidegrees(&path));
+            Some(TemperatureSensor {
+                label,
+                temperature,
+                max: None,
+                critical,
+            })
+        })
+        .collect()
+}
+
+/// PIDs of all processes visible in procfs
+pub fn list_pids(paths: &HostPaths) -> io::Result<Vec<u32>> {
+    Ok(std::fs::read_dir(&paths.proc)?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect())
+}
+
+/// The fields of `/proc/<pid>/stat` we use
+#[derive(Debug, Clone)]
+pub struct PidStat {
+    pub pid: u32,
+    pub comm: String,
+    /// One-letter state such as `R`, `S`, `D` or `Z`
+    pub state: char,
+    pub ppid: u32,
+    /// utime + stime in clock ticks
+    pub cpu_ticks: u64,
+    pub num_threads: u64,
+    /// Clock ticks after boot when the process started
+    pub start_ticks: u64,
+    /// Resident set size in bytes
+    pub rss: u64,
+}
+
+pub fn read_pid_stat(paths: &HostPaths, pid: u32) -> io::Result<PidStat> {
+    let content = std::fs::read_to_string(paths.proc(pid.to_string()).join("stat"))?;
+    parse_pid_stat(pid, &content).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed stat"))
+}
+
+fn parse_pid_stat(pid: u32, content: &str) -> Option<PidStat> {
+    // comm is wrapped in parentheses and may itself contain spaces or parentheses
+    let open = content.find('(')?;
+    let close = content.rfind(')')?;
+    let comm = content[open + 1..close].to_string();
+    let fields: Vec<&str> = content[close + 1..].split_whitespace().collect();
+    // Indices are offset by 3 from the man page numbering (pid, comm, state start at 1)
+    let field = |n: usize| -> Option<u64> { fields.get(n - 3)?.parse().ok() };
+    Some(PidStat {
+        pid,
+        comm,
+        state: fields.first()?.chars().next()?,
+        ppid: field(4)? as u32,
+        cpu_ticks: field(14)? + field(15)?,
+        num_threads: field(20)?,
+        start_ticks: field(22)?,
+        rss: field(24)? * page_size(),
+    })
+}
+
+/// Thread IDs of `pid` from `/proc/<pid>/task`
+pub fn list_tasks(paths: &HostPaths, pid: u32) -> io::Result<Vec<u32>> {
+    let mut tids: Vec<u32> = std::fs::read_dir(paths.proc(pid.to_string()).join("task"))?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect();
+    tids.sort_unstable();
+    Ok(tids)
+}
+
+/// `/proc/<pid>/task/<tid>/stat`; `pid` in the result is the thread ID
+pub fn read_task_stat(paths: &HostPaths, pid: u32, tid: u32) -> io::Result<PidStat> {
+    let path = paths.proc(pid.to_string()).join("task").join(tid.to_string()).join("stat");
+    let content = std::fs::read_to_string(path)?;
+    parse_pid_stat(tid, &content).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed stat"))
+}
+
+/// Kernel function `pid` is sleeping in, from `/proc/<pid>/wchan`; `None` when running
+pub fn read_wchan(paths: &HostPaths, pid: u32) -> Option<String> {
+    let wchan = std::fs::read_to_string(paths.proc(pid.to_string()).join("wchan")).ok()?;
+    (!wchan.is_empty() && wchan != "0").then_some(wchan)
+}
+
+/// Descriptor numbers and link targets from `/proc/<pid>/fd`, such as `/var/log/app.log`,
+/// `socket:[12345]` or `anon_inode:[eventfd]`. Descriptors closed while listing are skipped.
+pub fn read_fds(paths: &HostPaths, pid: u32) -> io::Result<Vec<(u32, String)>> {
+    let dir = paths.proc(pid.to_string()).join("fd");
+    let mut fds: Vec<(u32, String)> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let fd = entry.file_name().to_str()?.parse().ok()?;
+            let target = std::fs::read_link(entry.path()).ok()?;
+            Some((fd, target.to_string_lossy().into_owned()))
+        })
+        .collect();
+    fds.sort_unstable_by_key(|(fd, _)| *fd);
+    Ok(fds)
+}
+
+/// Soft and hard `Max open files` from `/proc/<pid>/limits`; `None` means unlimited
+pub fn read_open_files_limit(paths: &HostPaths, pid: u32) -> io::Result<(Option<u64>, Option<u64>)> {
+    let content = std::fs::read_to_string(paths.proc(pid.to_string()).join("limits"))?;
+    let line = content
+        .lines()
+        .find_map(|line| line.strip_prefix("Max open files"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no open files limit"))?;
+    let mut values = line.split_whitespace().map(|value| value.parse().ok());
+    Ok((values.next().flatten(), values.next().flatten()))
+}
+
+/// Memory totals of `pid` in bytes from `/proc/<pid>/smaps_rollup`, keyed as in the file (`Rss`, `Pss`, `Swap`, ...)
+pub fn read_smaps_rollup(paths: &HostPaths, pid: u32) -> io::Result<HashMap<String, u64>> {
+    let content = std::fs::read_to_string(paths.proc(pid.to_string()).join("smaps_rollup"))?;
+    Ok(content.lines().filter_map(parse_size_line).collect())
+}
+
+/// One entry of `/proc/<pid>/smaps`, sizes in bytes
+#[derive(Debug, Clone)]
+pub struct SmapsMapping {
+    /// Mapped file or pseudo path such as `[heap]`; empty for anonymous memory
+    pub path: String,
+    pub size: u64,
+    pub rss: u64,
+    pub pss: u64,
+    pub swap: u64,
+}
+
+pub fn read_smaps(paths: &HostPaths, pid: u32) -> io::Result<Vec<SmapsMapping>> {
+    let content = std::fs::read_to_string(paths.proc(pid.to_string()).join("smaps"))?;
+    let mut mappings: Vec<SmapsMapping> = Vec::new();
+    for line in content.lines() {
+        // Header lines are `start-end perms offset dev inode [path]`; field lines start with `Key:`
+        let first = line.split_whitespace().next().unwrap_or_default();
+        if !first.ends_with(':') {
+            let path = line.split_whitespace().skip(5).collect::<Vec<_>>().join(" ");
+            mappings.push(SmapsMapping { path, size: 0, rss: 0, pss: 0, swap: 0 });
+            continue;
+        }
+        let (Some(mapping), Some((key, value))) = (mappings.last_mut(), parse_size_line(line)) else {
+            continue;
+        };
+        match key.as_str() {
+            "Size" => mapping.size = value,
+            "Rss" => mapping.rss = value,
+            "Pss" => mapping.pss = value,
+            "Swap" => mapping.swap = value,
+            _ => {}
+        }
+    }
+    Ok(mappings)
+}
+
+pub fn clock_ticks() -> u64 {
+    // SAFETY: sysconf has no preconditions
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as u64,
+        _ => 100,
+    }
+}
+
+pub fn page_size() -> u64 {
+    // SAFETY: sysconf has no preconditions
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as u64,
+        _ => 4096,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_an_overlay_root_but_not_other_overlays() {
+        let mount = |mount_point: &str, fs_type: &str| MountEntry {
+            device: "overlay".to_string(),
+            mount_point: mount_point.to_string(),
+            fs_type: fs_type.to_string(),
+            options: Vec::new(),
+        };
+        let filter = MountFilter::default();
+        assert!(!filter.excludes(&mount("/", "overlay")));
+        assert!(filter.excludes(&mount("/var/lib/docker/overlay2/abc/merged", "overlay")));
+        assert!(filter.excludes(&mount("/run", "tmpfs")));
+        assert!(!filter.excludes(&mount("/home", "ext4")));
+        // Mount-point globs still apply to `/`
+        let filter = MountFilter {
+            fs_types: Vec::new(),
+            mount_points: vec!["/".to_string()],
+        };
+        assert!(filter.excludes(&mount("/", "ext4")));
+    }
+
+    #[test]
+    fn reads_the_effective_uid_from_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = HostPaths::new(Some(dir.path().to_path_buf()), None, None);
+        write(
+            dir.path().join("42/status"),
+            "Name:\tpasswd\nUmask:\t0022\nState:\tS (sleeping)\nUid:\t1000\t0\t0\t0\nGid:\t1000\t1000\t1000\t1000\n",
+        );
+        assert_eq!(read_pid_uid(&paths, 42).unwrap(), 0);
+        assert!(read_pid_uid(&paths, 43).is_err());
+        assert_eq!(parse_status_uid("Name:\tx\n"), None);
+    }
+
+    fn write(path: PathBuf, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn sys_paths(dir: &Path) -> HostPaths {
+        HostPaths::new(None, Some(dir.join("sys")), Some(dir.to_path_buf()))
+    }
+
+    #[test]
+    fn reads_hwmon_and_thermal_zone_temperatures() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = sys_paths(dir.path());
+        let hwmon = paths.sys("class/hwmon/hwmon0");
+        write(hwmon.join("name"), "coretemp\n");
+        write(hwmon.join("temp1_input"), "45000\n");
+        write(hwmon.join("temp1_label"), "Package id 0\n");
+        write(hwmon.join("temp1_max"), "80000\n");
+        write(hwmon.join("temp1_crit"), "100000\n");
+        write(hwmon.join("temp2_input"), "41500\n");
+        let zone = paths.sys("class/thermal/thermal_zone0");
+        write(zone.join("type"), "x86_pkg_temp\n");
+        write(zone.join("temp"), "47000\n");
+        write(zone.join("trip_point_0_type"), "passive\n");
+        write(zone.join("trip_point_0_temp"), "90000\n");
+        write(zone.join("trip_point_1_type"), "critical\n");
+        write(zone.join("trip_point_1_temp"), "105000\n");
+
+        let sensors = read_temperatures(&paths);
+        let summary: Vec<_> = sensors
+            .iter()
+            .map(|s| (s.label.as_str(), s.temperature, s.max, s.critical))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("coretemp Package id 0", 45.0, Some(80.0), Some(100.0)),
+                ("coretemp temp2", 41.5, None, None),
+                ("x86_pkg_temp", 47.0, None, Some(105.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_thermal_zones_registered_as_hwmon_chips() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = sys_paths(dir.path());
+        write(paths.sys("class/hwmon/hwmon0/name"), "acpitz\n");
+        write(paths.sys("class/hwmon/hwmon0/temp1_input"), "27800\n");
+        write(paths.sys("class/thermal/thermal_zone0/type"), "acpitz\n");
+        write(paths.sys("class/thermal/thermal_zone0/temp"), "27800\n");
+
+        let labels: Vec<_> = read_temperatures(&paths).into_iter().map(|s| s.label).collect();
+        assert_eq!(labels, ["acpitz temp1"]);
+    }
+
+    #[test]
+    fn missing_sysfs_has_no_sensors() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read_temperatures(&sys_paths(dir.path())).is_empty());
+    }
+}
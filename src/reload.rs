@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::sync::watch;
+use tracing_subscriber::{EnvFilter, Registry, reload};
+
+use crate::cfg::{self, Config};
+use crate::shutdown::Shutdown;
+
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Re-read the config file on SIGHUP and publish the result to every subscriber.
+/// Listener addresses and the server mode are kept as-is since they need a restart.
+/// `file_vars` is what the file held at startup, to log which keys changed.
+#[cfg(unix)]
+pub async fn listen_for_reload(
+    path: PathBuf,
+    mut file_vars: HashMap<String, String>,
+    config_tx: watch::Sender<Config>,
+    log_filter: LogFilterHandle,
+    shutdown: Shutdown,
+) {
+    let mut hangup = match sighup() {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            tracing::warn!("Config reload on SIGHUP unavailable: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            Some(()) = hangup.recv() => {}
+            _ = shutdown.wait() => break,
+        }
+
+        tracing::info!("Received SIGHUP, reloading {}", path.display());
+        let current = config_tx.borrow().clone();
+        let vars = match cfg::read_file(&path) {
+            Ok(vars) => vars,
+            Err(e) => {
+                tracing::error!("Config reload failed, keeping current settings: {:#}", e);
+                continue;
+            }
+        };
+        let mut config = match Config::from_vars(&vars) {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::error!("Config reload failed, keeping current settings: {:#}", e);
+                continue;
+            }
+        };
+
+        let ignored = config.restart_required(&current);
+        if !ignored.is_empty() {
+            tracing::warn!("Changes to {} require a restart and were ignored", ignored.join(", "));
+        }
+        config.keep_startup_settings(&current);
+        if let Err(e) = config.validate() {
+            tracing::error!("Config reload failed, keeping current settings: {:#}", e);
+            continue;
+        }
+
+        if config.log_filter != current.log_filter {
+            match EnvFilter::try_new(&config.log_filter) {
+                Ok(filter) => match log_filter.reload(filter) {
+                    Ok(()) => tracing::info!("Log filter changed to {}", config.log_filter),
+                    Err(e) => tracing::error!("Failed to apply log filter: {}", e),
+                },
+                Err(e) => {
+                    tracing::error!("Invalid log filter {:?}, keeping {:?}: {}", config.log_filter, current.log_filter, e);
+                    config.log_filter = current.log_filter;
+                }
+            }
+        }
+
+        let (changed, overridden) = cfg::changed_keys(&file_vars, &vars);
+        for key in &changed {
+            tracing::info!("Config {} changed", key);
+        }
+        if !overridden.is_empty() {
+            tracing::warn!(
+                "Changes to {} were ignored since the environment sets them",
+                overridden.join(", ")
+            );
+        }
+        file_vars = vars;
+
+        config_tx.send_replace(config);
+        tracing::info!("Configuration reloaded");
+    }
+}
+
+#[cfg(unix)]
+fn sighup() -> std::io::Result<tokio::signal::unix::Signal> {
+    use tokio::signal::unix::{SignalKind, signal};
+    signal(SignalKind::hangup())
+}
+
+#[cfg(not(unix))]
+pub async fn listen_for_reload(
+    _path: PathBuf,
+    _file_vars: HashMap<String, String>,
+    _config_tx: watch::Sender<Config>,
+    _log_filter: LogFilterHandle,
+    _shutdown: Shutdown,
+) {
+    tracing::warn!("Config reload on SIGHUP is not supported on this platform");
+}
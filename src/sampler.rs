@@ -0,0 +1,47 @@
+use std::sync::Arc;
+use tokio::sync::watch;
+
+use crate::cfg::Config;
+use crate::service::AppState;
+use crate::shutdown::Shutdown;
+use crate::systemd;
+
+/// Refresh the shared snapshot every `sample_interval` until shutdown.
+/// The interval, mount exclusions, process control policy, alert rules and webhooks are re-read whenever the config is reloaded, and systemd is
+/// told the service is ready once the first sample is in.
+pub async fn run(app_state: Arc<AppState>, mut config: watch::Receiver<Config>, shutdown: Shutdown) {
+    let mut interval = config.borrow_and_update().sample_interval;
+    tracing::info!("Sampling system information every {:?}", interval);
+
+    let mut ready = false;
+    loop {
+        let state = app_state.clone();
+        match tokio::task::spawn_blocking(move || state.sample()).await {
+            Ok(Ok(_)) => {
+                if !ready {
+                    systemd::notify_ready();
+                    ready = true;
+                }
+            }
+            Ok(Err(e)) => tracing::warn!("Failed to sample system information: {}", e),
+            Err(e) => tracing::error!("Sampler task panicked: {}", e),
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            Ok(()) = config.changed() => {
+                let current = config.borrow_and_update();
+                app_state.set_mount_filter(current.mount_filter.clone());
+                app_state.set_control_policy(current.control_policy.clone());
+                app_state.set_alert_rules(current.alert_rules.clone());
+                app_state.set_webhooks(current.webhooks.clone());
+                let new_interval = current.sample_interval;
+                if new_interval != interval {
+                    tracing::info!("Sample interval changed to {:?}", new_interval);
+                    interval = new_interval;
+                }
+            }
+            _ = shutdown.wait() => break,
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::Context;
+use axum::extract::ConnectInfo;
+use hyper::{Request, Response, body::Incoming};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::{
+        conn::auto::Builder,
+        graceful::{GracefulShutdown, Watcher},
+    },
+    service::TowerToHyperService,
+};
+use std::{
+    error::Error as StdError,
+    fmt,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, UnixListener},
+    task::JoinSet,
+};
+use tower::{Service, ServiceExt};
+
+use crate::shutdown::Shutdown;
+
+type BoxError = Box<dyn StdError + Send + Sync>;
+
+/// Credentials of the process on the other end of a Unix socket connection,
+/// attached to each request received over it
+#[derive(Debug, Clone, Copy)]
+pub struct PeerCred {
+    pub uid: u32,
+}
+
+pub enum Listener {
+    Tcp(TcpListener),
+    /// `path` is set when we created the socket file and should remove it on shutdown
+    Unix { listener: UnixListener, path: Option<PathBuf> },
+}
+
+impl Listener {
+    pub async fn bind_tcp(addr: SocketAddr) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("failed to bind {}", addr))?;
+        Ok(Self::Tcp(listener))
+    }
+
+    /// Bind a Unix socket at `path`, replacing a stale socket file left by a previous run,
+    /// and apply the configured permissions and ownership
+    pub fn bind_unix(path: &Path, mode: Option<u32>, owner: Option<(Option<u32>, Option<u32>)>) -> anyhow::Result<Self> {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+        if let Ok(meta) = std::fs::symlink_metadata(path) {
+            if !meta.file_type().is_socket() {
+                anyhow::bail!("{} exists and is not a socket", path.display());
+            }
+            std::fs::remove_file(path).with_context(|| format!("failed to remove stale socket {}", path.display()))?;
+        }
+
+        let listener = UnixListener::bind(path).with_context(|| format!("failed to bind {}", path.display()))?;
+        if let Some(mode) = mode {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+                .with_context(|| format!("failed to set mode {:o} on {}", mode, path.display()))?;
+        }
+        if let Some((uid, gid)) = owner {
+            std::os::unix::fs::chown(path, uid, gid)
+                .with_context(|| format!("failed to change owner of {}", path.display()))?;
+        }
+
+        Ok(Self::Unix {
+            listener,
+            path: Some(path.to_path_buf()),
+        })
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => write!(f, "tcp://{}", addr),
+                Err(_) => write!(f, "tcp://<unknown>"),
+            },
+            Self::Unix { listener, .. } => match listener.local_addr().ok().and_then(|addr| addr.as_pathname().map(Path::to_path_buf)) {
+                Some(path) => write!(f, "unix://{}", path.display()),
+                None => write!(f, "unix://<unnamed>"),
+            },
+        }
+    }
+}
+
+/// Serve HTTP/1 and HTTP/2 connections from every listener until `shutdown` fires,
+/// then stop accepting and give in-flight connections `drain_timeout` to finish.
+///
+/// TCP requests carry the peer address as `ConnectInfo<SocketAddr>` and Unix socket
+/// requests carry a [`PeerCred`], so middlewares can key on who is calling.
+pub async fn serve<S, B>(
+    name: &'static str,
+    listeners: Vec<Listener>,
+    service: S,
+    shutdown: Shutdown,
+    drain_timeout: Duration,
+) -> anyhow::Result<()>
+where
+    S: Service<Request<Incoming>, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    B: hyper::body::Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    anyhow::ensure!(!listeners.is_empty(), "{} server has no listeners configured", name);
+
+    let graceful = Arc::new(GracefulShutdown::new());
+    let mut accept_loops = JoinSet::new();
+    for listener in listeners {
+        tracing::info!("{} server listening on {}", name, listener);
+        accept_loops.spawn(accept_loop(
+            name,
+            listener,
+            service.clone(),
+            graceful.clone(),
+            shutdown.clone(),
+        ));
+    }
+    while let Some(res) = accept_loops.join_next().await {
+        if let Err(e) = res {
+            tracing::error!("{} accept loop panicked: {}", name, e);
+        }
+    }
+
+    let graceful = Arc::into_inner(graceful).expect("accept loops hold no references after exiting");
+    drain(name, graceful, drain_timeout).await;
+    Ok(())
+}
+
+async fn accept_loop<S, B>(
+    name: &'static str,
+    listener: Listener,
+    service: S,
+    graceful: Arc<GracefulShutdown>,
+    shutdown: Shutdown,
+) where
+    S: Service<Request<Incoming>, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    B: hyper::body::Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    match listener {
+        Listener::Tcp(listener) => loop {
+            let (stream, remote_addr) = tokio::select! {
+                _ = shutdown.wait() => break,
+                accept = listener.accept() => match accept {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        tracing::warn!("{} server failed to accept connection: {}", name, e);
+                        continue;
+                    }
+                },
+            };
+            let service = service.clone().map_request(move |mut req: Request<Incoming>| {
+                req.extensions_mut().insert(ConnectInfo(remote_addr));
+                req
+            });
+            tokio::spawn(serve_connection(name, stream, service, graceful.watcher()));
+        },
+        Listener::Unix { listener, path } => {
+            loop {
+                let stream = tokio::select! {
+                    _ = shutdown.wait() => break,
+                    accept = listener.accept() => match accept {
+                        Ok((stream, _)) => stream,
+                        Err(e) => {
+                            tracing::warn!("{} server failed to accept connection: {}", name, e);
+                            continue;
+                        }
+                    },
+                };
+                let peer = match stream.peer_cred() {
+                    Ok(cred) => PeerCred { uid: cred.uid() },
+                    Err(e) => {
+                        tracing::warn!("{} server could not read peer credentials, dropping connection: {}", name, e);
+                        continue;
+                    }
+                };
+                let service = service.clone().map_request(move |mut req: Request<Incoming>| {
+                    req.extensions_mut().insert(peer);
+                    req
+                });
+                tokio::spawn(serve_connection(name, stream, service, graceful.watcher()));
+            }
+            drop(listener);
+            if let Some(path) = path
+                && let Err(e) = std::fs::remove_file(&path)
+            {
+                tracing::warn!("Failed to remove socket {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+async fn serve_connection<I, S, B>(name: &'static str, stream: I, service: S, watcher: Watcher)
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    S: Service<Request<Incoming>, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    B: hyper::body::Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    let builder = Builder::new(TokioExecutor::new());
+    let conn = builder.serve_connection(TokioIo::new(stream), TowerToHyperService::new(service));
+    if let Err(e) = watcher.watch(conn).await {
+        tracing::debug!("{} connection closed with error: {}", name, e);
+    }
+}
+
+async fn drain(name: &'static str, graceful: GracefulShutdown, drain_timeout: Duration) {
+    let in_flight = graceful.count();
+    if in_flight == 0 {
+        tracing::info!("{} server stopped", name);
+        return;
+    }
+
+    tracing::info!("{} server draining {} connection(s)", name, in_flight);
+    tokio::select! {
+        _ = graceful.shutdown() => {
+            tracing::info!("{} server stopped", name);
+        }
+        _ = tokio::time::sleep(drain_timeout) => {
+            tracing::warn!(
+                "{} server drain timed out after {:?}, dropping remaining connections",
+                name,
+                drain_timeout
+            );
+        }
+    }
+}
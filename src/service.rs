@@ -1,28 +1,636 @@
-use chrono::Utc;
-use std::sync::{Arc, Mutex};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
 use sysinfo::System;
 
 use crate::models::{
-    SystemInfo, SystemOverview, CpuInfo, CpuCore, MemoryInfo, ProcessSummary, ProcessInfo,
+    SystemInfo, SystemOverview, CpuInfo, CpuCore, CpuTimes, LoadAverage, TaskCounts, MemoryInfo,
+    MemoryDetail, HugePages, VmCounters,
+    DiskInfo, DiskIoInfo, ComponentInfo, NetworkInfo, TcpStats, SocketInfo, PressureInfo, ResourcePressure, PressureStall, PressureReport, CgroupPressure,
+    ProcessSummary, ProcessHealth, ZombieProcess, BlockedProcess, ProcessInfo, ProcessGroup, ProcessDetail, ProcessAction, ProcessEvent, ProcessFiles, OpenFile, ProcessMaps, MemoryRollup,
+    MemoryMapping, ProcessThreads, ThreadInfo, Alert, AlertRuleInfo, HistorySeries, HistorySeriesInfo, Forecast, UserInfo, SessionInfo, UsersReport, CgroupInfo, CgroupCpu, CgroupMemory, CgroupIo,
 };
+use crate::alerts::{AlertEngine, AlertRule};
+use crate::containers::ContainerTracker;
+use crate::control::{self, Action, ControlPolicy};
+use crate::events::{Cursor, ProcessEvents};
+use crate::forecast::{self, Resource};
+use crate::history::{HistoryStore, Resolution};
+use crate::notify::{Notifier, WebhookConfig};
+use crate::procfs::{self, HostPaths, MountFilter};
+
+/// What [`AppState::get_processes`] groups processes by
+#[derive(Debug, Clone, Copy)]
+pub enum GroupBy {
+    Name,
+    Exe,
+    User,
+    Cgroup,
+}
+
+impl std::str::FromStr for GroupBy {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(Self::Name),
+            "exe" => Ok(Self::Exe),
+            "user" => Ok(Self::User),
+            "cgroup" => Ok(Self::Cgroup),
+            other => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("unknown group_by {:?}, expected name, exe, user or cgroup", other),
+            )),
+        }
+    }
+}
+
+/// Criteria for [`AppState::get_sockets`]; unset fields match everything
+#[derive(Debug, Default)]
+pub struct SocketFilter {
+    /// Matches either the local or the remote port
+    pub port: Option<u16>,
+    /// State name such as `listen` or `established`, case-insensitive
+    pub state: Option<String>,
+    pub pid: Option<u32>,
+}
+
+/// Entries listed by the per-process sub-resources when the caller doesn't ask for a number
+pub const DEFAULT_FILES_LIMIT: usize = 100;
+pub const DEFAULT_MAPS_LIMIT: usize = 20;
+pub const DEFAULT_THREADS_LIMIT: usize = 100;
+/// Cap on list lengths in per-process sub-resources, whatever the caller asks for
+pub const MAX_LIST_LIMIT: usize = 1000;
 
 #[derive(Clone)]
 pub struct AppState {
     system: Arc<Mutex<System>>,
+    latest: Arc<RwLock<Option<SystemInfo>>>,
+    /// Every process from the latest sample, for grouping on request
+    processes: Arc<RwLock<Vec<ProcessInfo>>>,
+    last_sampled: Arc<Mutex<Option<Instant>>>,
+    paths: HostPaths,
+    mount_filter: Arc<RwLock<MountFilter>>,
+    control_policy: Arc<RwLock<ControlPolicy>>,
+    trackers: Arc<Mutex<Trackers>>,
+    events: Arc<ProcessEvents>,
+    alerts: Arc<AlertEngine>,
+    notifier: Arc<Notifier>,
+    /// `None` unless `HISTORY_DB` is set
+    history: Option<Arc<HistoryStore>>,
+}
+
+/// Counters remembered from the previous sample to turn cumulative values into rates
+struct Trackers {
+    host_processes: HostProcessTracker,
+    cpu_stat: Option<procfs::CpuStat>,
+    disk_stats: Option<(Instant, HashMap<String, procfs::DiskStat>)>,
+    net_dev: Option<(Instant, HashMap<String, procfs::NetDevStat>)>,
+    containers: ContainerTracker,
+    /// When each process currently in `D` state was first seen in it
+    blocked_since: HashMap<u32, Instant>,
 }
 
 impl AppState {
-    pub fn new() -> Self {
+    pub fn new(
+        paths: HostPaths,
+        mount_filter: MountFilter,
+        runtime_socket: Option<PathBuf>,
+        control_policy: ControlPolicy,
+        alert_rules: Vec<AlertRule>,
+        webhooks: WebhookConfig,
+        history: Option<HistoryStore>,
+    ) -> Self {
         let mut sys = System::new_all();
         sys.refresh_all();
+        if paths.is_overridden() {
+            tracing::info!(
+                "Reading host information from proc={}, sys={}, root={}",
+                paths.proc.display(),
+                paths.sys.display(),
+                paths.root.display()
+            );
+        }
         Self {
             system: Arc::new(Mutex::new(sys)),
+            latest: Arc::new(RwLock::new(None)),
+            processes: Arc::new(RwLock::new(Vec::new())),
+            last_sampled: Arc::new(Mutex::new(None)),
+            paths,
+            mount_filter: Arc::new(RwLock::new(mount_filter)),
+            control_policy: Arc::new(RwLock::new(control_policy)),
+            trackers: Arc::new(Mutex::new(Trackers {
+                host_processes: HostProcessTracker::default(),
+                cpu_stat: None,
+                disk_stats: None,
+                net_dev: None,
+                containers: ContainerTracker::new(runtime_socket),
+                blocked_since: HashMap::new(),
+            })),
+            events: Arc::new(ProcessEvents::new()),
+            alerts: Arc::new(AlertEngine::new(alert_rules)),
+            notifier: Arc::new(Notifier::new(webhooks)),
+            history: history.map(Arc::new),
+        }
+    }
+
+    /// Swap the mount exclusions after a config reload; applies from the next sample
+    pub fn set_mount_filter(&self, mount_filter: MountFilter) {
+        *self.mount_filter.write().unwrap() = mount_filter;
+    }
+
+    /// Swap the process control policy after a config reload
+    pub fn set_control_policy(&self, control_policy: ControlPolicy) {
+        *self.control_policy.write().unwrap() = control_policy;
+    }
+
+    /// Swap the alert rules after a config reload
+    pub fn set_alert_rules(&self, rules: Vec<AlertRule>) {
+        self.alerts.set_rules(rules);
+    }
+
+    /// Swap the webhook settings after a config reload
+    pub fn set_webhooks(&self, webhooks: WebhookConfig) {
+        self.notifier.set_config(webhooks);
+    }
+
+    /// Pending and firing alerts, plus recently resolved ones if `include_resolved`
+    pub fn get_alerts(&self, include_resolved: bool) -> Vec<Alert> {
+        self.alerts.alerts(include_resolved)
+    }
+
+    pub fn get_alert_rules(&self) -> Vec<AlertRuleInfo> {
+        self.alerts
+            .rules()
+            .into_iter()
+            .map(|rule| AlertRuleInfo { name: rule.name, expr: rule.expr })
+            .collect()
+    }
+
+    pub fn control_policy(&self) -> ControlPolicy {
+        self.control_policy.read().unwrap().clone()
+    }
+
+    /// Signal or renice `pid` on behalf of `actor` if the policy allows it.
+    /// Every attempt past the enabled check is audited, refusals included.
+    pub fn control_process(&self, pid: u32, action: Action, actor: &str) -> anyhow::Result<ProcessAction> {
+        let policy = self.control_policy();
+        if !policy.enabled {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "process control is disabled; set PROCESS_CONTROL_ENABLED=true to allow it",
+            )
+            .into());
+        }
+
+        let stat = procfs::read_pid_stat(&self.paths, pid).ok();
+        let start_ticks = stat.as_ref().map(|stat| stat.start_ticks);
+        let target = stat.map(|stat| {
+            let uid = procfs::read_pid_uid(&self.paths, pid).ok();
+            control::Target {
+                pid,
+                name: stat.comm,
+                uid,
+                user: uid.and_then(|uid| procfs::read_passwd(&self.paths).remove(&uid)),
+                exe: procfs::read_exe(&self.paths, pid).ok(),
+            }
+        });
+        let outcome = match &target {
+            None => Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("process {} not found", pid))),
+            Some(_) if pid <= 1 || pid == std::process::id() => Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("refusing to act on pid {}", pid),
+            )),
+            Some(target) => policy
+                .check(target)
+                .and_then(|()| self.ensure_same_process(pid, start_ticks))
+                .and_then(|()| control::perform(pid, action)),
+        };
+        control::audit(&policy, actor, pid, target.as_ref(), action, &outcome);
+        outcome?;
+
+        let target = target.expect("outcome is an error without a target");
+        Ok(ProcessAction {
+            pid,
+            name: target.name,
+            user: target.user,
+            exe: target.exe,
+            action: action.to_string(),
+        })
+    }
+
+    /// Refuse to act if `pid` exited or was reused since the policy was checked against it
+    fn ensure_same_process(&self, pid: u32, start_ticks: Option<u64>) -> std::io::Result<()> {
+        match procfs::read_pid_stat(&self.paths, pid) {
+            Ok(stat) if Some(stat.start_ticks) == start_ticks => Ok(()),
+            Ok(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("process {} was replaced by another process with the same pid", pid),
+            )),
+            Err(_) => Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("process {} not found", pid))),
+        }
+    }
+
+    /// Latest sampled snapshot, collected on the spot if the sampler hasn't produced one yet
+    pub fn get_system_info(&self) -> anyhow::Result<SystemInfo> {
+        if let Some(info) = self.latest.read().unwrap().as_ref() {
+            return Ok(info.clone());
+        }
+        self.sample()
+    }
+
+    /// Refresh the system and publish the result as the latest snapshot
+    pub fn sample(&self) -> anyhow::Result<SystemInfo> {
+        let (info, processes) = self.collect()?;
+        self.events.observe(&processes, &self.paths);
+        let transitions = self.alerts.evaluate(&info);
+        self.notifier.notify(&transitions, info.system.host_name.as_deref());
+        if let Some(history) = &self.history {
+            history.record(&info);
+        }
+        *self.latest.write().unwrap() = Some(info.clone());
+        *self.processes.write().unwrap() = processes;
+        *self.last_sampled.lock().unwrap() = Some(Instant::now());
+        Ok(info)
+    }
+
+    fn history(&self) -> anyhow::Result<&HistoryStore> {
+        self.history.as_deref().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "metric history is disabled, set HISTORY_DB to enable it",
+            )
+            .into()
+        })
+    }
+
+    /// Stored values of `metric` between `from` and `to`, one series per matching instance
+    pub fn get_history(
+        &self,
+        metric: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        resolution: Option<Resolution>,
+    ) -> anyhow::Result<Vec<HistorySeries>> {
+        self.history()?.query(metric, from, to, resolution)
+    }
+
+    /// Every metric series recorded in the history store
+    pub fn get_history_series(&self) -> anyhow::Result<Vec<HistorySeriesInfo>> {
+        self.history()?.series()
+    }
+
+    /// Time until disks, memory or swap reach `threshold` percent, fitted to the stored usage
+    /// over the last `window`. Soonest first; resources that aren't filling up come last.
+    pub fn get_forecast(
+        &self,
+        resource: Option<Resource>,
+        window: std::time::Duration,
+        threshold: f64,
+    ) -> anyhow::Result<Vec<Forecast>> {
+        if !(threshold > 0.0 && threshold <= 100.0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("threshold {} out of range, expected a percentage above 0 up to 100", threshold),
+            )
+            .into());
+        }
+        let history = self.history()?;
+        let now = Utc::now();
+        let from = chrono::Duration::from_std(window)
+            .ok()
+            .and_then(|window| now.checked_sub_signed(window))
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "window is out of range"))?;
+        // Without swap there is nothing to exhaust
+        let has_swap = self.get_system_info().map_or(true, |info| info.memory.swap_total > 0);
+
+        let mut forecasts = Vec::new();
+        for kind in Resource::ALL {
+            if resource.is_some_and(|resource| resource != kind) || (kind == Resource::Swap && !has_swap) {
+                continue;
+            }
+            for series in history.query(kind.metric(), from, now, None)? {
+                forecasts.extend(forecast::forecast(kind, &series, threshold, now));
+            }
         }
+        forecasts.sort_by_key(|forecast| forecast.eta_secs.unwrap_or(u64::MAX));
+        Ok(forecasts)
     }
 
-    pub fn get_system_info(&self) -> Result<SystemInfo, Box<dyn std::error::Error>> {
+    /// Buffered process start/exit events after `since`, optionally for one process name
+    pub fn get_process_events(&self, since: Option<Cursor>, name: Option<&str>) -> Vec<ProcessEvent> {
+        self.events.since(since, name)
+    }
+
+    /// Live process events from the next sample on
+    pub fn subscribe_process_events(&self) -> tokio::sync::broadcast::Receiver<ProcessEvent> {
+        self.events.subscribe()
+    }
+
+    /// Process rankings from the latest snapshot, plus the top `limit` groups when `group_by` is set.
+    /// Grouping by executable or cgroup reads procfs for every process, so call it via `run_blocking`.
+    pub fn get_processes(&self, group_by: Option<GroupBy>, limit: usize) -> anyhow::Result<ProcessSummary> {
+        let mut summary = self.get_system_info()?.processes;
+        if let Some(group_by) = group_by {
+            // Group a copy so the sampler isn't kept waiting on the lock
+            let processes = self.processes.read().unwrap().clone();
+            let mut groups = group_processes(&processes, group_by, &self.paths);
+            groups.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
+            summary.top_cpu_groups = Some(groups.iter().take(limit).cloned().collect());
+            groups.sort_by_key(|g| std::cmp::Reverse(g.memory));
+            summary.top_memory_groups = Some(groups.into_iter().take(limit).collect());
+        }
+        Ok(summary)
+    }
+
+    /// Memory from the latest snapshot, with the kernel breakdown read on the spot if `detail` is set
+    pub fn get_memory(&self, detail: bool) -> anyhow::Result<MemoryInfo> {
+        let mut memory = self.get_system_info()?.memory;
+        if detail {
+            memory.detail = Some(memory_detail(&self.paths)?);
+        }
+        Ok(memory)
+    }
+
+    /// Host pressure from the latest snapshot plus, if requested, one cgroup read on the spot
+    pub fn get_pressure(&self, cgroup: Option<&str>) -> anyhow::Result<PressureReport> {
+        let host = self.get_system_info()?.pressure;
+        let cgroup = match cgroup {
+            Some(path) => {
+                let dir = procfs::cgroup_dir(&self.paths, path)?;
+                Some(CgroupPressure {
+                    path: path.to_string(),
+                    pressure: read_pressure(|resource| dir.join(format!("{}.pressure", resource))),
+                })
+            }
+            None => None,
+        };
+        Ok(PressureReport { host, cgroup })
+    }
+
+    /// TCP and UDP sockets with their owning processes, read on the spot
+    pub fn get_sockets(&self, filter: &SocketFilter) -> anyhow::Result<Vec<SocketInfo>> {
+        if let Some(state) = &filter.state
+            && !SOCKET_STATES.iter().any(|known| known.eq_ignore_ascii_case(state))
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("unknown socket state {:?}, expected one of {}", state, SOCKET_STATES.join(", ")),
+            )
+            .into());
+        }
+
+        let owners = procfs::socket_owners(&self.paths)
+            .with_context(|| format!("failed to list processes in {}", self.paths.proc.display()))?;
+        let mut names = HashMap::new();
+        let mut sockets = Vec::new();
+        for protocol in ["tcp", "tcp6", "udp", "udp6"] {
+            // IPv6 tables are missing when the kernel has IPv6 disabled
+            let Ok(entries) = procfs::read_sockets(&self.paths, protocol) else {
+                continue;
+            };
+            for entry in entries {
+                let state = socket_state(protocol, &entry);
+                let pid = owners.get(&entry.inode).copied();
+                if filter.port.is_some_and(|port| port != entry.local.port() && port != entry.remote.port())
+                    || filter.state.as_ref().is_some_and(|s| !s.eq_ignore_ascii_case(state))
+                    || filter.pid.is_some_and(|p| Some(p) != pid)
+                {
+                    continue;
+                }
+                let process_name = pid.and_then(|pid| {
+                    names
+                        .entry(pid)
+                        .or_insert_with(|| procfs::read_comm(&self.paths, pid))
+                        .clone()
+                });
+                sockets.push(SocketInfo {
+                    protocol: protocol.to_string(),
+                    local_address: entry.local.ip().to_string(),
+                    local_port: entry.local.port(),
+                    remote_address: entry.remote.ip().to_string(),
+                    remote_port: entry.remote.port(),
+                    state: state.to_string(),
+                    uid: entry.uid,
+                    pid,
+                    process_name,
+                });
+            }
+        }
+        Ok(sockets)
+    }
+
+    /// Per-user usage from the latest snapshot plus the current utmp sessions
+    pub fn get_users(&self) -> anyhow::Result<UsersReport> {
+        let users = self.get_system_info()?.users;
+        let sessions = procfs::read_utmp(&self.paths)
+            .into_iter()
+            .map(|session| SessionInfo {
+                user: session.user,
+                tty: session.line,
+                host: Some(session.host).filter(|host| !host.is_empty()),
+                pid: session.pid,
+                login_time: session.login_time,
+            })
+            .collect();
+        Ok(UsersReport { users, sessions })
+    }
+
+    /// Details of one process, read on the spot
+    pub fn get_process(&self, pid: u32) -> anyhow::Result<ProcessDetail> {
+        let stat = self.process_stat(pid)?;
+        let ticks_per_sec = procfs::clock_ticks();
+        let boot_time = procfs::read_boot_time(&self.paths).unwrap_or_default();
+        Ok(ProcessDetail {
+            pid,
+            ppid: stat.ppid,
+            name: stat.comm,
+            // Unreadable for other users' processes without privileges
+            cmdline: procfs::read_cmdline(&self.paths, pid).unwrap_or_default(),
+            state: stat.state.to_string(),
+            threads: stat.num_threads,
+            memory: stat.rss,
+            cpu_time: stat.cpu_ticks as f64 / ticks_per_sec as f64,
+            start_time: boot_time + stat.start_ticks / ticks_per_sec,
+            cgroup: procfs::read_pid_cgroup(&self.paths, pid).ok().flatten(),
+        })
+    }
+
+    /// Open descriptors of `pid`, listing at most `limit` of them
+    pub fn get_process_files(&self, pid: u32, limit: usize) -> anyhow::Result<ProcessFiles> {
+        self.process_stat(pid)?;
+        let mut errors = HashMap::new();
+        let (soft_limit, hard_limit) = field(&mut errors, "limits", procfs::read_open_files_limit(&self.paths, pid))
+            .unwrap_or_default();
+
+        let Some(fds) = field(&mut errors, "files", procfs::read_fds(&self.paths, pid)) else {
+            return Ok(ProcessFiles {
+                pid,
+                count: None,
+                soft_limit,
+                hard_limit,
+                by_type: None,
+                files: None,
+                truncated: false,
+                errors,
+            });
+        };
+        let files: Vec<OpenFile> = fds
+            .into_iter()
+            .map(|(fd, target)| OpenFile { fd, kind: fd_kind(&target).to_string(), target })
+            .collect();
+        let mut by_type = HashMap::new();
+        for file in &files {
+            *by_type.entry(file.kind.clone()).or_insert(0) += 1;
+        }
+        Ok(ProcessFiles {
+            pid,
+            count: Some(files.len()),
+            soft_limit,
+            hard_limit,
+            by_type: Some(by_type),
+            truncated: files.len() > limit,
+            files: Some(files.into_iter().take(limit).collect()),
+            errors,
+        })
+    }
+
+    /// Memory totals of `pid` and its `limit` largest mappings by RSS
+    pub fn get_process_maps(&self, pid: u32, limit: usize) -> anyhow::Result<ProcessMaps> {
+        self.process_stat(pid)?;
+        let mut errors = HashMap::new();
+        let rollup = field(&mut errors, "rollup", procfs::read_smaps_rollup(&self.paths, pid)).map(|values| {
+            let value = |key: &str| values.get(key).copied().unwrap_or_default();
+            MemoryRollup {
+                rss: value("Rss"),
+                pss: value("Pss"),
+                pss_anon: values.get("Pss_Anon").copied(),
+                pss_file: values.get("Pss_File").copied(),
+                pss_shmem: values.get("Pss_Shmem").copied(),
+                shared_clean: value("Shared_Clean"),
+                shared_dirty: value("Shared_Dirty"),
+                private_clean: value("Private_Clean"),
+                private_dirty: value("Private_Dirty"),
+                swap: value("Swap"),
+                swap_pss: value("SwapPss"),
+            }
+        });
+
+        let mappings = field(&mut errors, "mappings", procfs::read_smaps(&self.paths, pid)).map(|smaps| {
+            let mut by_path: HashMap<String, MemoryMapping> = HashMap::new();
+            for smap in smaps {
+                let path = if smap.path.is_empty() { "[anon]".to_string() } else { smap.path };
+                let mapping = by_path.entry(path.clone()).or_insert_with(|| MemoryMapping {
+                    path,
+                    count: 0,
+                    size: 0,
+                    rss: 0,
+                    pss: 0,
+                    swap: 0,
+                });
+                mapping.count += 1;
+                mapping.size += smap.size;
+                mapping.rss += smap.rss;
+                mapping.pss += smap.pss;
+                mapping.swap += smap.swap;
+            }
+            let mut mappings: Vec<MemoryMapping> = by_path.into_values().collect();
+            mappings.sort_by_key(|m| std::cmp::Reverse(m.rss));
+            mappings
+        });
+
+        Ok(ProcessMaps {
+            pid,
+            rollup,
+            total_mappings: mappings.as_ref().map(Vec::len),
+            truncated: mappings.as_ref().is_some_and(|m| m.len() > limit),
+            mappings: mappings.map(|m| m.into_iter().take(limit).collect()),
+            errors,
+        })
+    }
+
+    /// Threads of `pid`, the `limit` with the most CPU time first
+    pub fn get_process_threads(&self, pid: u32, limit: usize) -> anyhow::Result<ProcessThreads> {
+        self.process_stat(pid)?;
+        let mut errors = HashMap::new();
+        let ticks_per_sec = procfs::clock_ticks() as f64;
+        let uptime = procfs::read_uptime(&self.paths).unwrap_or_default() as f64;
+
+        let threads = field(&mut errors, "threads", procfs::list_tasks(&self.paths, pid)).map(|tids| {
+            let mut threads: Vec<ThreadInfo> = tids
+                .into_iter()
+                // Threads that exit while we list them are skipped
+                .filter_map(|tid| procfs::read_task_stat(&self.paths, pid, tid).ok())
+                .map(|stat| {
+                    let cpu_time = stat.cpu_ticks as f64 / ticks_per_sec;
+                    let lifetime = uptime - stat.start_ticks as f64 / ticks_per_sec;
+                    ThreadInfo {
+                        tid: stat.pid,
+                        name: stat.comm,
+                        state: stat.state.to_string(),
+                        cpu_time,
+                        avg_cpu_usage: if lifetime > 0.0 { cpu_time / lifetime * 100.0 } else { 0.0 },
+                    }
+                })
+                .collect();
+            threads.sort_by(|a, b| b.cpu_time.partial_cmp(&a.cpu_time).unwrap_or(std::cmp::Ordering::Equal));
+            threads
+        });
+
+        Ok(ProcessThreads {
+            pid,
+            count: threads.as_ref().map(Vec::len),
+            truncated: threads.as_ref().is_some_and(|t| t.len() > limit),
+            threads: threads.map(|t| t.into_iter().take(limit).collect()),
+            errors,
+        })
+    }
+
+    /// `/proc/<pid>/stat`, with a 404-friendly error for processes that don't exist
+    fn process_stat(&self, pid: u32) -> std::io::Result<procfs::PidStat> {
+        procfs::read_pid_stat(&self.paths, pid).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                std::io::Error::new(std::io::ErrorKind::NotFound, format!("process {} not found", pid))
+            }
+            _ => e,
+        })
+    }
+
+    /// The cgroup v2 tree below `path` (the root by default), `depth` levels deep
+    pub fn get_cgroups(&self, path: Option<&str>, depth: Option<usize>) -> anyhow::Result<CgroupInfo> {
+        let path = path.unwrap_or("/");
+        let dir = procfs::cgroup_dir(&self.paths, path)?;
+        Ok(cgroup_node(&dir, format!("/{}", path.trim_matches('/')), depth.unwrap_or(usize::MAX)))
+    }
+
+    /// When the last sample finished; `None` until the first one completes
+    pub fn last_sampled(&self) -> Option<Instant> {
+        *self.last_sampled.lock().unwrap()
+    }
+
+    fn collect(&self) -> anyhow::Result<(SystemInfo, Vec<ProcessInfo>)> {
         let mut sys = self.system.lock().unwrap();
-        sys.refresh_all();
+        if self.paths.is_overridden() {
+            // Only CPU load comes from sysinfo when reading a mounted host
+            sys.refresh_cpu();
+        } else {
+            sys.refresh_all();
+        }
+
+        let mut trackers = self.trackers.lock().unwrap();
+
+        // Time breakdown since the previous sample (since boot on the first one)
+        let cpu_stat = procfs::read_cpu_stat(&self.paths).ok();
+        let prev_cpu_stat = std::mem::replace(&mut trackers.cpu_stat, cpu_stat.clone());
+        let prev_core = |name: &str| {
+            prev_cpu_stat
+                .as_ref()
+                .and_then(|prev| prev.cores.get(name).copied())
+                .unwrap_or_default()
+        };
 
         let cpus: Vec<CpuCore> = sys
             .cpus()
@@ -31,67 +639,708 @@ impl AppState {
                 name: cpu.name().to_string(),
                 usage: cpu.cpu_usage(),
                 frequency: cpu.frequency(),
+                times: cpu_stat
+                    .as_ref()
+                    .and_then(|stat| stat.cores.get(cpu.name()))
+                    .map(|ticks| cpu_times(&ticks.saturating_sub(&prev_core(cpu.name())))),
             })
             .collect();
 
-        let mut processes: Vec<_> = sys.processes().values().collect();
-        processes.sort_by(|a, b| {
-            b.cpu_usage()
-                .partial_cmp(&a.cpu_usage())
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-        let top_cpu_processes: Vec<ProcessInfo> = processes
-            .iter()
-            .take(10)
-            .map(|process| ProcessInfo {
-                pid: process.pid().as_u32(),
-                name: process.name().to_string(),
-                cpu_usage: process.cpu_usage(),
-                memory: process.memory(),
+        let disk_io = procfs::read_diskstats(&self.paths)
+            .map(|stats| {
+                let now = Instant::now();
+                let prev = trackers.disk_stats.replace((now, stats.clone()));
+                let prev = prev.as_ref().map(|(at, prev)| (now.duration_since(*at).as_secs_f64(), prev));
+                disk_io_rates(&stats, prev)
             })
-            .collect();
+            .unwrap_or_default();
 
-        processes.sort_by(|a, b| b.memory().cmp(&a.memory()));
-        let top_memory_processes: Vec<ProcessInfo> = processes
-            .iter()
-            .take(10)
-            .map(|process| ProcessInfo {
-                pid: process.pid().as_u32(),
-                name: process.name().to_string(),
-                cpu_usage: process.cpu_usage(),
-                memory: process.memory(),
+        let networks = procfs::read_net_dev(&self.paths)
+            .map(|stats| {
+                let now = Instant::now();
+                let prev = trackers.net_dev.replace((now, stats.clone()));
+                let prev = prev.as_ref().map(|(at, prev)| (now.duration_since(*at).as_secs_f64(), prev));
+                network_rates(&stats, prev)
             })
-            .collect();
+            .unwrap_or_default();
 
-        Ok(SystemInfo {
+        let load_average = procfs::read_loadavg(&self.paths)
+            .ok()
+            .map(|(one, five, fifteen)| LoadAverage { one, five, fifteen });
+
+        let (system, memory, mut processes) = if self.paths.is_overridden() {
+            (
+                host_overview(&self.paths),
+                host_memory(&self.paths)?,
+                trackers.host_processes.collect(&self.paths)?,
+            )
+        } else {
+            (local_overview(), local_memory(&sys), local_processes(&sys))
+        };
+        let containers = trackers
+            .containers
+            .attribute(&mut processes, &self.paths);
+        let users = attribute_users(&mut processes, &self.paths);
+        let health = process_health(&processes, &self.paths, &mut trackers.blocked_since);
+
+        let info = SystemInfo {
             timestamp: Utc::now(),
-            system: SystemOverview {
-                name: System::name(),
-                kernel_version: System::kernel_version(),
-                os_version: System::os_version(),
-                host_name: System::host_name(),
-                uptime: System::uptime(),
-                boot_time: System::boot_time(),
-            },
+            system,
             cpu: CpuInfo {
                 global_usage: sys.global_cpu_info().cpu_usage(),
                 cores: cpus,
                 physical_core_count: sys.physical_core_count(),
+                load_average,
+                tasks: cpu_stat.as_ref().map(|stat| TaskCounts {
+                    runnable: stat.procs_running,
+                    blocked: stat.procs_blocked,
+                }),
+                times: cpu_stat.as_ref().map(|stat| {
+                    let prev = prev_cpu_stat.as_ref().map(|prev| prev.total).unwrap_or_default();
+                    cpu_times(&stat.total.saturating_sub(&prev))
+                }),
             },
-            memory: MemoryInfo {
-                total: sys.total_memory(),
-                available: sys.available_memory(),
-                used: sys.used_memory(),
-                free: sys.free_memory(),
-                swap_total: sys.total_swap(),
-                swap_used: sys.used_swap(),
-                swap_free: sys.free_swap(),
-            },
-            processes: ProcessSummary {
-                total_count: sys.processes().len(),
-                top_cpu_processes,
-                top_memory_processes,
+            memory,
+            disks: collect_disks(&self.paths, &self.mount_filter.read().unwrap()),
+            disk_io,
+            pressure: host_pressure(&self.paths),
+            components: collect_components(&self.paths),
+            networks,
+            tcp: tcp_stats(&self.paths),
+            processes: summarize_processes(processes.clone(), health),
+            containers,
+            users,
+        };
+        Ok((info, processes))
+    }
+}
+
+/// Run a service call that blocks on disk, such as a history query, off the async runtime
+pub async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> anyhow::Result<T> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!("task failed: {}", e)))
+}
+
+/// Express tick deltas as a percentage of the elapsed CPU time
+fn cpu_times(delta: &procfs::CpuTicks) -> CpuTimes {
+    let total = delta.total().max(1) as f32;
+    let pct = |ticks: u64| ticks as f32 / total * 100.0;
+    CpuTimes {
+        user: pct(delta.user),
+        nice: pct(delta.nice),
+        system: pct(delta.system),
+        idle: pct(delta.idle),
+        iowait: pct(delta.iowait),
+        irq: pct(delta.irq),
+        softirq: pct(delta.softirq),
+        steal: pct(delta.steal),
+        guest: pct(delta.guest),
+        guest_nice: pct(delta.guest_nice),
+    }
+}
+
+/// Rates since the previous read; zero on the first sample or for devices that just appeared
+fn disk_io_rates(
+    stats: &HashMap<String, procfs::DiskStat>,
+    prev: Option<(f64, &HashMap<String, procfs::DiskStat>)>,
+) -> Vec<DiskIoInfo> {
+    const SECTOR_SIZE: f64 = 512.0;
+
+    let mut devices: Vec<DiskIoInfo> = stats
+        .iter()
+        .map(|(name, stat)| {
+            let (elapsed, delta) = match prev.and_then(|(elapsed, prev)| Some((elapsed, prev.get(name)?))) {
+                Some((elapsed, prev)) if elapsed > 0.0 => (elapsed, stat.saturating_sub(prev)),
+                _ => (0.0, procfs::DiskStat::default()),
+            };
+            let rate = |count: u64| if elapsed > 0.0 { count as f64 / elapsed } else { 0.0 };
+            let ios = delta.reads + delta.writes;
+            DiskIoInfo {
+                name: name.clone(),
+                read_bytes_per_sec: rate(delta.sectors_read) * SECTOR_SIZE,
+                write_bytes_per_sec: rate(delta.sectors_written) * SECTOR_SIZE,
+                reads_per_sec: rate(delta.reads),
+                writes_per_sec: rate(delta.writes),
+                in_flight: stat.in_flight,
+                await_ms: if ios > 0 {
+                    (delta.read_ticks + delta.write_ticks) as f64 / ios as f64
+                } else {
+                    0.0
+                },
+                // io_ticks are milliseconds, so busy ms per second / 10 is a percentage
+                utilization: (rate(delta.io_ticks) / 10.0).min(100.0),
+            }
+        })
+        .collect();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    devices
+}
+
+/// Same as [`disk_io_rates`] for network interfaces
+fn network_rates(
+    stats: &HashMap<String, procfs::NetDevStat>,
+    prev: Option<(f64, &HashMap<String, procfs::NetDevStat>)>,
+) -> HashMap<String, NetworkInfo> {
+    stats
+        .iter()
+        .map(|(name, stat)| {
+            let (elapsed, delta) = match prev.and_then(|(elapsed, prev)| Some((elapsed, prev.get(name)?))) {
+                Some((elapsed, prev)) if elapsed > 0.0 => (elapsed, stat.saturating_sub(prev)),
+                _ => (0.0, procfs::NetDevStat::default()),
+            };
+            let rate = |count: u64| if elapsed > 0.0 { count as f64 / elapsed } else { 0.0 };
+            let info = NetworkInfo {
+                received: stat.rx_bytes,
+                transmitted: stat.tx_bytes,
+                packets_received: stat.rx_packets,
+                packets_transmitted: stat.tx_packets,
+                errors_on_received: stat.rx_errors,
+                errors_on_transmitted: stat.tx_errors,
+                received_bytes_per_sec: rate(delta.rx_bytes),
+                transmitted_bytes_per_sec: rate(delta.tx_bytes),
+                packets_received_per_sec: rate(delta.rx_packets),
+                packets_transmitted_per_sec: rate(delta.tx_packets),
+            };
+            (name.clone(), info)
+        })
+        .collect()
+}
+
+fn tcp_stats(paths: &HostPaths) -> Option<TcpStats> {
+    let snmp = procfs::read_net_counters(paths, "snmp").ok()?;
+    // TcpExt lives in a separate file that some kernels don't have
+    let netstat = procfs::read_net_counters(paths, "netstat").unwrap_or_default();
+    let snmp = |name: &str| snmp.get(&format!("Tcp:{}", name)).copied().unwrap_or_default();
+    let ext = |name: &str| netstat.get(&format!("TcpExt:{}", name)).copied().unwrap_or_default();
+    Some(TcpStats {
+        active_opens: snmp("ActiveOpens"),
+        passive_opens: snmp("PassiveOpens"),
+        attempt_fails: snmp("AttemptFails"),
+        established_resets: snmp("EstabResets"),
+        current_established: snmp("CurrEstab"),
+        in_segments: snmp("InSegs"),
+        out_segments: snmp("OutSegs"),
+        retransmitted_segments: snmp("RetransSegs"),
+        in_errors: snmp("InErrs"),
+        out_resets: snmp("OutRsts"),
+        listen_overflows: ext("ListenOverflows"),
+        listen_drops: ext("ListenDrops"),
+    })
+}
+
+/// Names for the kernel's `TCP_*` states, indexed by state number, plus `UNCONN` for UDP
+const SOCKET_STATES: [&str; 13] = [
+    "UNKNOWN",
+    "ESTABLISHED",
+    "SYN_SENT",
+    "SYN_RECV",
+    "FIN_WAIT1",
+    "FIN_WAIT2",
+    "TIME_WAIT",
+    "CLOSE",
+    "CLOSE_WAIT",
+    "LAST_ACK",
+    "LISTEN",
+    "CLOSING",
+    "UNCONN",
+];
+
+fn socket_state(protocol: &str, entry: &procfs::SocketEntry) -> &'static str {
+    // UDP sockets that were never connect()ed sit in TCP_CLOSE
+    if protocol.starts_with("udp") && entry.state == 7 {
+        return "UNCONN";
+    }
+    match entry.state {
+        1..=11 => SOCKET_STATES[entry.state as usize],
+        _ => "UNKNOWN",
+    }
+}
+
+fn local_overview() -> SystemOverview {
+    SystemOverview {
+        name: System::name(),
+        kernel_version: System::kernel_version(),
+        os_version: System::os_version(),
+        host_name: System::host_name(),
+        uptime: System::uptime(),
+        boot_time: System::boot_time(),
+    }
+}
+
+fn host_overview(paths: &HostPaths) -> SystemOverview {
+    let mut os_release = procfs::read_os_release(paths);
+    SystemOverview {
+        name: os_release.remove("NAME"),
+        kernel_version: procfs::read_kernel_release(paths),
+        os_version: os_release.remove("VERSION_ID"),
+        host_name: procfs::read_hostname(paths),
+        uptime: procfs::read_uptime(paths).unwrap_or_default(),
+        boot_time: procfs::read_boot_time(paths).unwrap_or_default(),
+    }
+}
+
+fn local_memory(sys: &System) -> MemoryInfo {
+    MemoryInfo {
+        total: sys.total_memory(),
+        available: sys.available_memory(),
+        used: sys.used_memory(),
+        free: sys.free_memory(),
+        swap_total: sys.total_swap(),
+        swap_used: sys.used_swap(),
+        swap_free: sys.free_swap(),
+        detail: None,
+    }
+}
+
+fn host_memory(paths: &HostPaths) -> anyhow::Result<MemoryInfo> {
+    let meminfo = procfs::read_meminfo(paths)
+        .with_context(|| format!("failed to read {}", paths.proc("meminfo").display()))?;
+    let field = |key: &str| meminfo.get(key).copied().unwrap_or_default();
+    let total = field("MemTotal");
+    let available = field("MemAvailable");
+    let swap_total = field("SwapTotal");
+    let swap_free = field("SwapFree");
+    Ok(MemoryInfo {
+        total,
+        available,
+        used: total.saturating_sub(available),
+        free: field("MemFree"),
+        swap_total,
+        swap_used: swap_total.saturating_sub(swap_free),
+        swap_free,
+        detail: None,
+    })
+}
+
+fn memory_detail(paths: &HostPaths) -> anyhow::Result<MemoryDetail> {
+    let meminfo = procfs::read_meminfo(paths)
+        .with_context(|| format!("failed to read {}", paths.proc("meminfo").display()))?;
+    let vmstat = procfs::read_vmstat(paths)
+        .with_context(|| format!("failed to read {}", paths.proc("vmstat").display()))?;
+    let field = |key: &str| meminfo.get(key).copied().unwrap_or_default();
+    let counter = |key: &str| vmstat.get(key).copied().unwrap_or_default();
+    Ok(MemoryDetail {
+        buffers: field("Buffers"),
+        cached: field("Cached"),
+        shared: field("Shmem"),
+        slab_reclaimable: field("SReclaimable"),
+        slab_unreclaimable: field("SUnreclaim"),
+        dirty: field("Dirty"),
+        writeback: field("Writeback"),
+        committed_as: field("Committed_AS"),
+        commit_limit: field("CommitLimit"),
+        huge_pages: HugePages {
+            total: field("HugePages_Total"),
+            free: field("HugePages_Free"),
+            reserved: field("HugePages_Rsvd"),
+            surplus: field("HugePages_Surp"),
+            page_size: field("Hugepagesize"),
+        },
+        vm: VmCounters {
+            page_faults: counter("pgfault"),
+            major_page_faults: counter("pgmajfault"),
+            swap_in: counter("pswpin"),
+            swap_out: counter("pswpout"),
+            oom_kills: vmstat.get("oom_kill").copied(),
+        },
+    })
+}
+
+/// The value of a per-field read, or `None` with the error recorded under `name`
+fn field<T>(errors: &mut HashMap<String, String>, name: &str, result: std::io::Result<T>) -> Option<T> {
+    result.map_err(|e| errors.insert(name.to_string(), e.to_string())).ok()
+}
+
+/// Descriptor type from its `/proc/<pid>/fd` link target
+fn fd_kind(target: &str) -> &'static str {
+    if target.starts_with("socket:") {
+        "socket"
+    } else if target.starts_with("pipe:") {
+        "pipe"
+    } else if target.starts_with("anon_inode:") {
+        "anon_inode"
+    } else if target.starts_with("/dev/") {
+        "device"
+    } else if target.starts_with('/') {
+        "file"
+    } else {
+        "other"
+    }
+}
+
+fn local_processes(sys: &System) -> Vec<ProcessInfo> {
+    sys.processes()
+        .values()
+        // sysinfo lists threads as processes too; /proc only lists thread group leaders
+        .filter(|process| process.thread_kind() != Some(sysinfo::ThreadKind::Userland))
+        .map(|process| ProcessInfo {
+            pid: process.pid().as_u32(),
+            name: process.name().to_string(),
+            cpu_usage: process.cpu_usage(),
+            memory: process.memory(),
+            uid: None,
+            user: None,
+            container: None,
+            start_time: Some(process.start_time()),
+            state: Some(state_letter(process.status())),
+            ppid: process.parent().map(|ppid| ppid.as_u32()),
+        })
+        .collect()
+}
+
+/// Fill in each process's owner and sum usage per user, heaviest CPU users first
+fn attribute_users(processes: &mut [ProcessInfo], paths: &HostPaths) -> Vec<UserInfo> {
+    let names = procfs::read_passwd(paths);
+    let mut users: HashMap<u32, UserInfo> = HashMap::new();
+    for process in processes.iter_mut() {
+        let Ok(uid) = procfs::read_pid_uid(paths, process.pid) else {
+            continue;
+        };
+        process.uid = Some(uid);
+        process.user = names.get(&uid).cloned();
+
+        let user = users.entry(uid).or_insert_with(|| UserInfo {
+            uid,
+            name: process.user.clone(),
+            process_count: 0,
+            cpu_usage: 0.0,
+            memory: 0,
+        });
+        user.process_count += 1;
+        user.cpu_usage += process.cpu_usage;
+        user.memory += process.memory;
+    }
+
+    let mut users: Vec<UserInfo> = users.into_values().collect();
+    users.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
+    users
+}
+
+fn group_processes(processes: &[ProcessInfo], group_by: GroupBy, paths: &HostPaths) -> Vec<ProcessGroup> {
+    let mut groups: HashMap<String, ProcessGroup> = HashMap::new();
+    for process in processes {
+        let key = match group_by {
+            GroupBy::Name => process.name.clone(),
+            // Kernel threads have no executable, and other users' need privileges; name them like ps does
+            GroupBy::Exe => procfs::read_exe(paths, process.pid).unwrap_or_else(|_| format!("[{}]", process.name)),
+            GroupBy::User => match (&process.user, process.uid) {
+                (Some(user), _) => user.clone(),
+                (None, Some(uid)) => uid.to_string(),
+                (None, None) => "unknown".to_string(),
             },
+            GroupBy::Cgroup => procfs::read_pid_cgroup(paths, process.pid)
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| "unknown".to_string()),
+        };
+        let group = groups.entry(key.clone()).or_insert_with(|| ProcessGroup {
+            key,
+            process_count: 0,
+            cpu_usage: 0.0,
+            memory: 0,
+        });
+        group.process_count += 1;
+        group.cpu_usage += process.cpu_usage;
+        group.memory += process.memory;
+    }
+    groups.into_values().collect()
+}
+
+fn summarize_processes(mut processes: Vec<ProcessInfo>, health: ProcessHealth) -> ProcessSummary {
+    let total_count = processes.len();
+
+    processes.sort_by(|a, b| {
+        b.cpu_usage
+            .partial_cmp(&a.cpu_usage)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let top_cpu_processes: Vec<ProcessInfo> = processes.iter().take(10).cloned().collect();
+
+    processes.sort_by_key(|p| std::cmp::Reverse(p.memory));
+    let top_memory_processes: Vec<ProcessInfo> = processes.iter().take(10).cloned().collect();
+
+    ProcessSummary {
+        total_count,
+        top_cpu_processes,
+        top_memory_processes,
+        top_cpu_groups: None,
+        top_memory_groups: None,
+        health,
+    }
+}
+
+/// Most zombies and blocked processes listed in [`ProcessHealth`]
+const HEALTH_LIST_LIMIT: usize = 100;
+
+/// Count processes by state and pick out zombies and `D`-state processes.
+/// `blocked_since` carries first sightings of `D` state between samples.
+/// Only the wait channel of processes in `D` state is read; states and parents come with the sample.
+fn process_health(processes: &[ProcessInfo], paths: &HostPaths, blocked_since: &mut HashMap<u32, Instant>) -> ProcessHealth {
+    let names: HashMap<u32, &str> = processes.iter().map(|process| (process.pid, process.name.as_str())).collect();
+    let now = Instant::now();
+
+    let mut health = ProcessHealth::default();
+    let mut still_blocked = HashMap::new();
+    for process in processes {
+        let Some(state) = process.state else {
+            continue;
+        };
+        *health.states.entry(state_name(state).to_string()).or_insert(0) += 1;
+        match state {
+            'Z' => {
+                let ppid = process.ppid.unwrap_or_default();
+                health.zombies.push(ZombieProcess {
+                    pid: process.pid,
+                    name: process.name.clone(),
+                    ppid,
+                    parent_name: names.get(&ppid).map(|name| name.to_string()),
+                })
+            }
+            'D' => {
+                let since = blocked_since.get(&process.pid).copied().unwrap_or(now);
+                still_blocked.insert(process.pid, since);
+                health.blocked.push(BlockedProcess {
+                    pid: process.pid,
+                    name: process.name.clone(),
+                    wchan: procfs::read_wchan(paths, process.pid),
+                    blocked_secs: now.duration_since(since).as_secs(),
+                });
+            }
+            _ => {}
+        }
+    }
+    *blocked_since = still_blocked;
+
+    health.zombies.sort_by_key(|zombie| zombie.pid);
+    health.zombies.truncate(HEALTH_LIST_LIMIT);
+    health.blocked.sort_by_key(|blocked| std::cmp::Reverse(blocked.blocked_secs));
+    health.blocked.truncate(HEALTH_LIST_LIMIT);
+    health
+}
+
+/// The `/proc/<pid>/stat` letter sysinfo parsed its status from
+fn state_letter(status: sysinfo::ProcessStatus) -> char {
+    use sysinfo::ProcessStatus;
+    match status {
+        ProcessStatus::Run => 'R',
+        ProcessStatus::Sleep => 'S',
+        ProcessStatus::UninterruptibleDiskSleep => 'D',
+        ProcessStatus::Zombie => 'Z',
+        ProcessStatus::Stop => 'T',
+        ProcessStatus::Tracing => 't',
+        ProcessStatus::Idle => 'I',
+        ProcessStatus::Dead => 'X',
+        ProcessStatus::Parked => 'P',
+        ProcessStatus::Waking => 'W',
+        ProcessStatus::Wakekill => 'K',
+        ProcessStatus::LockBlocked => 'L',
+        ProcessStatus::Unknown(code) => char::from_u32(code).unwrap_or('?'),
+    }
+}
+
+/// Readable name for a `/proc/<pid>/stat` state letter
+fn state_name(state: char) -> &'static str {
+    match state {
+        'R' => "running",
+        'S' => "sleeping",
+        'D' => "disk_sleep",
+        'Z' => "zombie",
+        'T' => "stopped",
+        't' => "tracing_stop",
+        'I' => "idle",
+        'X' | 'x' => "dead",
+        'P' => "parked",
+        'W' => "waking",
+        'K' => "wakekill",
+        _ => "unknown",
+    }
+}
+
+/// Computes per-process CPU usage from `/proc/<pid>/stat` tick deltas between samples
+#[derive(Default)]
+struct HostProcessTracker {
+    prev_ticks: HashMap<u32, u64>,
+    prev_at: Option<Instant>,
+}
+
+impl HostProcessTracker {
+    fn collect(&mut self, paths: &HostPaths) -> anyhow::Result<Vec<ProcessInfo>> {
+        let pids = procfs::list_pids(paths)
+            .with_context(|| format!("failed to list processes in {}", paths.proc.display()))?;
+        let now = Instant::now();
+        let elapsed = self.prev_at.map(|at| now.duration_since(at).as_secs_f64());
+        let ticks_per_sec = procfs::clock_ticks() as f64;
+        let boot_time = procfs::read_boot_time(paths);
+
+        let mut ticks = HashMap::with_capacity(pids.len());
+        let processes = pids
+            .into_iter()
+            // Processes can exit between listing and reading; just skip them
+            .filter_map(|pid| procfs::read_pid_stat(paths, pid).ok())
+            .map(|stat| {
+                let cpu_usage = match (elapsed, self.prev_ticks.get(&stat.pid)) {
+                    (Some(elapsed), Some(prev)) if elapsed > 0.0 => {
+                        (stat.cpu_ticks.saturating_sub(*prev) as f64 / ticks_per_sec / elapsed * 100.0) as f32
+                    }
+                    _ => 0.0,
+                };
+                ticks.insert(stat.pid, stat.cpu_ticks);
+                ProcessInfo {
+                    pid: stat.pid,
+                    name: stat.comm,
+                    cpu_usage,
+                    memory: stat.rss,
+                    uid: None,
+                    user: None,
+                    container: None,
+                    start_time: boot_time.map(|boot_time| boot_time + stat.start_ticks / procfs::clock_ticks()),
+                    state: Some(stat.state),
+                    ppid: Some(stat.ppid),
+                }
+            })
+            .collect();
+
+        self.prev_ticks = ticks;
+        self.prev_at = Some(now);
+        Ok(processes)
+    }
+}
+
+fn collect_components(paths: &HostPaths) -> Vec<ComponentInfo> {
+    procfs::read_temperatures(paths)
+        .into_iter()
+        .map(|sensor| ComponentInfo {
+            label: sensor.label,
+            temperature: sensor.temperature,
+            max: sensor.max,
+            critical: sensor.critical,
+        })
+        .collect()
+}
+
+fn cgroup_node(dir: &std::path::Path, path: String, depth: usize) -> CgroupInfo {
+    let cpu = procfs::read_key_values(&dir.join("cpu.stat")).ok().map(|stat| CgroupCpu {
+        usage_usec: stat.get("usage_usec").copied().unwrap_or_default(),
+        user_usec: stat.get("user_usec").copied().unwrap_or_default(),
+        system_usec: stat.get("system_usec").copied().unwrap_or_default(),
+        nr_periods: stat.get("nr_periods").copied(),
+        nr_throttled: stat.get("nr_throttled").copied(),
+        throttled_usec: stat.get("throttled_usec").copied(),
+    });
+    // The root cgroup has no memory.current, so its memory stays `None`
+    let memory = procfs::read_cgroup_value(&dir.join("memory.current"))
+        .ok()
+        .flatten()
+        .map(|current| CgroupMemory {
+            current,
+            max: procfs::read_cgroup_value(&dir.join("memory.max")).ok().flatten(),
+        });
+    let io = procfs::read_cgroup_io_stat(&dir.join("io.stat"))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(device, counters)| {
+            let counter = |key: &str| counters.get(key).copied().unwrap_or_default();
+            CgroupIo {
+                device,
+                read_bytes: counter("rbytes"),
+                write_bytes: counter("wbytes"),
+                read_ios: counter("rios"),
+                write_ios: counter("wios"),
+            }
         })
+        .collect();
+    let pids = procfs::read_cgroup_value(&dir.join("pids.current")).ok().flatten();
+
+    let children = if depth == 0 {
+        Vec::new()
+    } else {
+        procfs::cgroup_children(dir)
+            .into_iter()
+            .map(|child| {
+                let name = child.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                let child_path = match path.as_str() {
+                    "/" => format!("/{}", name),
+                    parent => format!("{}/{}", parent, name),
+                };
+                cgroup_node(&child, child_path, depth - 1)
+            })
+            .collect()
+    };
+
+    CgroupInfo {
+        path,
+        cpu,
+        memory,
+        io,
+        pids,
+        children,
     }
-}
\ No newline at end of file
+}
+
+/// Host-wide PSI, or `None` on kernels built without it or booted with `psi=0`
+fn host_pressure(paths: &HostPaths) -> Option<PressureInfo> {
+    let pressure = read_pressure(|resource| paths.proc("pressure").join(resource));
+    if pressure.cpu.is_none() && pressure.memory.is_none() && pressure.io.is_none() {
+        return None;
+    }
+    Some(pressure)
+}
+
+fn read_pressure(path_for: impl Fn(&str) -> std::path::PathBuf) -> PressureInfo {
+    let read = |resource: &str| {
+        let (some, full) = procfs::read_pressure_file(&path_for(resource)).ok()?;
+        let stall = |line: procfs::PressureLine| PressureStall {
+            avg10: line.avg10,
+            avg60: line.avg60,
+            avg300: line.avg300,
+            total: line.total,
+        };
+        Some(ResourcePressure {
+            some: some.map(stall),
+            full: full.map(stall),
+        })
+    };
+    PressureInfo {
+        cpu: read("cpu"),
+        memory: read("memory"),
+        io: read("io"),
+    }
+}
+
+/// Capacity of every real filesystem in the host's mount table
+fn collect_disks(paths: &HostPaths, filter: &MountFilter) -> Vec<DiskInfo> {
+    let mounts = match procfs::read_mounts(paths) {
+        Ok(mounts) => mounts,
+        Err(e) => {
+            tracing::warn!("Failed to read mount table: {}", e);
+            return Vec::new();
+        }
+    };
+
+    mounts
+        .into_iter()
+        .filter(|mount| !filter.excludes(mount))
+        .filter_map(|mount| {
+            let stats = procfs::statvfs(&paths.root(&mount.mount_point)).ok()?;
+            if stats.total == 0 {
+                return None;
+            }
+            Some(DiskInfo {
+                is_removable: procfs::is_removable(paths, &mount.device),
+                read_only: mount.options.iter().any(|o| o == "ro"),
+                name: mount.device,
+                mount_point: mount.mount_point,
+                fs_type: mount.fs_type,
+                mount_options: mount.options,
+                total_space: stats.total,
+                available_space: stats.available,
+                total_inodes: stats.inodes,
+                used_inodes: stats.inodes.saturating_sub(stats.inodes_free),
+                free_inodes: stats.inodes_free,
+            })
+        })
+        .collect()
+}
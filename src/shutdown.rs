@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Process-wide shutdown coordination shared by every server and background task
+#[derive(Clone)]
+pub struct Shutdown {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(false);
+        Self { tx: Arc::new(tx) }
+    }
+
+    /// Ask every listener to stop; calling it more than once is harmless
+    pub fn trigger(&self) {
+        self.tx.send_replace(true);
+    }
+
+    /// Resolve once shutdown has been requested
+    pub async fn wait(&self) {
+        let mut rx = self.tx.subscribe();
+        // The sender lives as long as `self`, so this can only fail if it was dropped
+        let _ = rx.wait_for(|triggered| *triggered).await;
+    }
+}
+
+/// Wait for SIGTERM or SIGINT, then trigger `shutdown`.
+/// A second signal while draining aborts the process immediately.
+pub async fn listen_for_signals(shutdown: Shutdown) {
+    let signal = wait_for_signal().await;
+    tracing::info!("Received {}, shutting down gracefully", signal);
+    shutdown.trigger();
+
+    let signal = wait_for_signal().await;
+    tracing::warn!("Received {} again, exiting without draining", signal);
+    std::process::exit(130);
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> &'static str {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut term = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+    let mut int = signal(SignalKind::interrupt()).expect("failed to install SIGINT handler");
+    tokio::select! {
+        _ = term.recv() => "SIGTERM",
+        _ = int.recv() => "SIGINT",
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> &'static str {
+    let _ = tokio::signal::ctrl_c().await;
+    "Ctrl-C"
+}
@@ -0,0 +1,118 @@
+use anyhow::Context;
+use sd_notify::NotifyState;
+use std::os::fd::{FromRawFd, RawFd};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use crate::cfg::Config;
+use crate::server::Listener;
+use crate::service::AppState;
+use crate::shutdown::Shutdown;
+
+/// Sockets handed over by systemd socket activation, split per server
+#[derive(Default)]
+pub struct ActivatedListeners {
+    pub rest: Vec<Listener>,
+    pub mcp: Vec<Listener>,
+}
+
+/// Take ownership of the sockets passed via `LISTEN_FDS`.
+///
+/// Sockets named `mcp` (`FileDescriptorName=mcp` in the socket unit) go to the
+/// MCP server, everything else to the REST API. Must run inside the runtime and
+/// before anything else opens file descriptors.
+pub fn take_listeners() -> anyhow::Result<ActivatedListeners> {
+    let mut activated = ActivatedListeners::default();
+    let fds = sd_notify::listen_fds_with_names(false).context("invalid socket activation environment")?;
+    for (fd, name) in fds {
+        let listener = listener_from_fd(fd).with_context(|| format!("unusable activated socket {} ({})", fd, name))?;
+        tracing::info!("Received socket {} ({}) from systemd", listener, name);
+        if name == "mcp" {
+            activated.mcp.push(listener);
+        } else {
+            activated.rest.push(listener);
+        }
+    }
+    Ok(activated)
+}
+
+fn listener_from_fd(fd: RawFd) -> anyhow::Result<Listener> {
+    let mut addr: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    // SAFETY: `addr` is large enough for any address family and `len` holds its size
+    if unsafe { libc::getsockname(fd, &mut addr as *mut _ as *mut libc::sockaddr, &mut len) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    // SAFETY: systemd passes these descriptors to us exclusively; ownership moves into the listener
+    match addr.ss_family as libc::c_int {
+        libc::AF_INET | libc::AF_INET6 => {
+            let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+            listener.set_nonblocking(true)?;
+            Ok(Listener::Tcp(tokio::net::TcpListener::from_std(listener)?))
+        }
+        libc::AF_UNIX => {
+            let listener = unsafe { std::os::unix::net::UnixListener::from_raw_fd(fd) };
+            listener.set_nonblocking(true)?;
+            Ok(Listener::Unix {
+                listener: tokio::net::UnixListener::from_std(listener)?,
+                path: None,
+            })
+        }
+        family => anyhow::bail!("unsupported address family {}", family),
+    }
+}
+
+/// Tell systemd the service is up; a no-op outside of a `Type=notify` unit
+pub fn notify_ready() {
+    if let Err(e) = sd_notify::notify(false, &[NotifyState::Ready]) {
+        tracing::warn!("Failed to notify systemd readiness: {}", e);
+    }
+}
+
+pub fn notify_stopping() {
+    if let Err(e) = sd_notify::notify(false, &[NotifyState::Stopping]) {
+        tracing::warn!("Failed to notify systemd shutdown: {}", e);
+    }
+}
+
+/// Ping the systemd watchdog for as long as the sampler keeps producing samples.
+///
+/// If sampling stalls (e.g. a wedged `System` lock) the pings stop and systemd
+/// restarts the unit once `WatchdogSec` elapses.
+pub async fn run_watchdog(app_state: Arc<AppState>, config: watch::Receiver<Config>, shutdown: Shutdown) {
+    let mut usec = 0;
+    if !sd_notify::watchdog_enabled(false, &mut usec) {
+        return;
+    }
+    let timeout = Duration::from_micros(usec);
+    tracing::info!("systemd watchdog enabled with timeout {:?}", timeout);
+
+    // Until the first sample, staleness counts from when the watchdog started
+    let started = Instant::now();
+    let mut stalled = false;
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(timeout / 2) => {}
+            _ = shutdown.wait() => break,
+        }
+
+        // Allow two missed sampling rounds before declaring the sampler stuck
+        let allowed = config.borrow().sample_interval * 2 + Duration::from_secs(5);
+        let progressing = app_state.last_sampled().unwrap_or(started).elapsed() <= allowed;
+
+        if progressing {
+            if stalled {
+                tracing::info!("Sampler recovered, resuming watchdog pings");
+                stalled = false;
+            }
+            if let Err(e) = sd_notify::notify(false, &[NotifyState::Watchdog]) {
+                tracing::warn!("Failed to ping systemd watchdog: {}", e);
+            }
+        } else if !stalled {
+            tracing::error!("Sampler has made no progress for over {:?}, withholding watchdog pings", allowed);
+            stalled = true;
+        }
+    }
+}